@@ -3,7 +3,42 @@ fn main() {
     static_link_faiss();
 
     #[cfg(not(feature = "static-faiss"))]
-    println!("cargo:rustc-link-lib=faiss_c");
+    {
+        build_shim(None);
+        println!("cargo:rustc-link-lib=faiss_c");
+        // the shim calls into the native library directly
+        println!("cargo:rustc-link-lib=faiss");
+    }
+}
+
+/// Compile the extensions to the C API in `shim/`.
+///
+/// The Faiss headers are looked up in `include_dir` if given,
+/// in `FAISS_INCLUDE_DIR` if set,
+/// and in the compiler's default include paths.
+fn build_shim(include_dir: Option<&std::path::Path>) {
+    println!("cargo:rerun-if-changed=shim");
+    println!("cargo:rerun-if-env-changed=FAISS_INCLUDE_DIR");
+    println!("cargo:rerun-if-env-changed=DOCS_RS");
+
+    // docs.rs does not have Faiss installed, nor does it link anything
+    if std::env::var_os("DOCS_RS").is_some() {
+        return;
+    }
+
+    let mut build = cc::Build::new();
+    build
+        .cpp(true)
+        .std("c++17")
+        .include("shim")
+        .file("shim/faiss_rs_shim.cpp");
+    if let Some(dir) = include_dir {
+        build.include(dir);
+    }
+    if let Some(dir) = std::env::var_os("FAISS_INCLUDE_DIR") {
+        build.include(dir);
+    }
+    build.compile("faiss_rs_shim");
 }
 
 #[cfg(feature = "static-faiss")]
@@ -108,6 +143,9 @@ fn static_link_faiss() {
     let profile = cfg.get_profile().to_owned();
     let dst = cfg.build();
 
+    // the shim needs to come before the libraries it depends on
+    build_shim(Some(std::path::Path::new("faiss")));
+
     let faiss_location = dst.join("lib");
 
     // CMake on Windows puts the C API library in a subfolder based on the build
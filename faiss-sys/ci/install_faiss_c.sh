@@ -2,7 +2,7 @@
 set -eu
 
 repo_url=https://github.com/facebookresearch/faiss
-repo_rev=v1.8.0
+repo_rev=v1.10.0
 
 git clone "$repo_url" faiss --branch "$repo_rev" --depth 1
 
@@ -29,6 +29,9 @@ cmake . -DFAISS_ENABLE_C_API=ON -DBUILD_SHARED_LIBS=ON \
 
 make
 cp -f "../rev_hash" faiss/libfaiss.so c_api/libfaiss_c.so "$HOME/.faiss_c/"
+# headers, for building the C API shim in faiss-sys
+mkdir -p "$HOME/.faiss_c/include"
+find faiss -name '*.h' -exec cp --parents {} "$HOME/.faiss_c/include/" \;
 
 echo "libfaiss_c.so (" `cat ../rev_hash` ") installed in $HOME/.faiss_c/"
 
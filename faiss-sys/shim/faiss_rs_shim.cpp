@@ -0,0 +1,83 @@
+/*
+ * Implementation of the extensions to the Faiss C API in `faiss_rs_shim.h`.
+ */
+
+#include "faiss_rs_shim.h"
+
+#include <exception>
+#include <stdexcept>
+
+#include <faiss/Index.h>
+#include <faiss/VectorTransform.h>
+#include <faiss/impl/DistanceComputer.h>
+#include <faiss/impl/FaissException.h>
+#include <faiss/impl/IDSelector.h>
+#include <faiss/index_io.h>
+
+// The last error of the C API, defined in `c_api/error_impl.cpp`
+// and reported by `faiss_get_last_error`
+extern thread_local std::exception_ptr faiss_last_exception;
+
+// Same error handling as `CATCH_AND_HANDLE` in `c_api/macros_impl.h`
+#define CATCH_AND_HANDLE                                               \
+    catch (faiss::FaissException & e) {                                \
+        faiss_last_exception = std::make_exception_ptr(e);             \
+        return -2;                                                     \
+    }                                                                  \
+    catch (std::exception & e) {                                       \
+        faiss_last_exception = std::make_exception_ptr(e);             \
+        return -4;                                                     \
+    }                                                                  \
+    catch (...) {                                                      \
+        faiss_last_exception = std::make_exception_ptr(                \
+                std::runtime_error("Unknown error"));                  \
+        return -1;                                                     \
+    }                                                                  \
+    return 0;
+
+int faiss_IDSelectorBitmap_new(
+        FaissIDSelectorBitmap** p_sel,
+        size_t n,
+        const uint8_t* bitmap) {
+    try {
+        *p_sel = reinterpret_cast<FaissIDSelectorBitmap*>(
+                new faiss::IDSelectorBitmap(n, bitmap));
+    }
+    CATCH_AND_HANDLE
+}
+
+int faiss_Index_get_distance_computer(
+        const FaissIndex* index,
+        FaissDistanceComputer** p_dc) {
+    try {
+        *p_dc = reinterpret_cast<FaissDistanceComputer*>(
+                reinterpret_cast<const faiss::Index*>(index)
+                        ->get_distance_computer());
+    }
+    CATCH_AND_HANDLE
+}
+
+int faiss_write_VectorTransform_fname(
+        const FaissVectorTransform* vt,
+        const char* fname) {
+    try {
+        faiss::write_VectorTransform(
+                reinterpret_cast<const faiss::VectorTransform*>(vt), fname);
+    }
+    CATCH_AND_HANDLE
+}
+
+#define DEFINE_VECTOR_TRANSFORM_DOWNCAST(name)                           \
+    Faiss##name* faiss_##name##_cast(FaissVectorTransform* vt) {         \
+        return reinterpret_cast<Faiss##name*>(dynamic_cast<faiss::name*>( \
+                reinterpret_cast<faiss::VectorTransform*>(vt)));         \
+    }
+
+DEFINE_VECTOR_TRANSFORM_DOWNCAST(RandomRotationMatrix)
+DEFINE_VECTOR_TRANSFORM_DOWNCAST(PCAMatrix)
+DEFINE_VECTOR_TRANSFORM_DOWNCAST(ITQMatrix)
+DEFINE_VECTOR_TRANSFORM_DOWNCAST(ITQTransform)
+DEFINE_VECTOR_TRANSFORM_DOWNCAST(OPQMatrix)
+DEFINE_VECTOR_TRANSFORM_DOWNCAST(RemapDimensionsTransform)
+DEFINE_VECTOR_TRANSFORM_DOWNCAST(NormalizationTransform)
+DEFINE_VECTOR_TRANSFORM_DOWNCAST(CenteringTransform)
@@ -0,0 +1,91 @@
+/*
+ * Extensions to the Faiss C API, for functionality which the upstream
+ * C interface does not provide yet.
+ *
+ * The declarations follow the conventions of the Faiss C API: functions
+ * which can fail return 0 on success and a nonzero error code otherwise,
+ * in which case the error message is available via `faiss_get_last_error`.
+ *
+ * The Rust declarations of these functions live in `src/shim.rs`,
+ * and need to be kept in sync with this file.
+ */
+
+#ifndef FAISS_RS_SHIM_H
+#define FAISS_RS_SHIM_H
+
+#include <stddef.h>
+#include <stdint.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+typedef struct FaissIndex_H FaissIndex;
+typedef struct FaissDistanceComputer_H FaissDistanceComputer;
+typedef struct FaissIDSelectorBitmap_H FaissIDSelectorBitmap;
+typedef struct FaissVectorTransform_H FaissVectorTransform;
+typedef FaissVectorTransform FaissRandomRotationMatrix;
+typedef FaissVectorTransform FaissPCAMatrix;
+typedef FaissVectorTransform FaissITQMatrix;
+typedef FaissVectorTransform FaissITQTransform;
+typedef FaissVectorTransform FaissOPQMatrix;
+typedef FaissVectorTransform FaissRemapDimensionsTransform;
+typedef FaissVectorTransform FaissNormalizationTransform;
+typedef FaissVectorTransform FaissCenteringTransform;
+
+/** Create a selector admitting the IDs whose bit is set in the given bitmap.
+ *
+ * ID `i` is selected iff `i / 8 < n` and bit number `i % 8` of
+ * `bitmap[i / 8]` is set. The bitmap is not copied, so it must outlive the
+ * selector.
+ *
+ * @param p_sel   output pointer to the new selector
+ * @param n       size of the bitmap in bytes
+ * @param bitmap  the bitmap
+ */
+int faiss_IDSelectorBitmap_new(
+        FaissIDSelectorBitmap** p_sel,
+        size_t n,
+        const uint8_t* bitmap);
+
+/** Create a distance computer over the vectors stored in an index.
+ * This is equivalent to `faiss::Index::get_distance_computer`, which not all
+ * index types implement. The distance computer must be freed with
+ * `faiss_DistanceComputer_free`, and it must not outlive the index.
+ *
+ * @param index  the index
+ * @param p_dc   output pointer to the new distance computer
+ */
+int faiss_Index_get_distance_computer(
+        const FaissIndex* index,
+        FaissDistanceComputer** p_dc);
+
+/** Write a vector transform to a file.
+ * This is equivalent to `faiss::write_VectorTransform` when a file path is
+ * given.
+ */
+int faiss_write_VectorTransform_fname(
+        const FaissVectorTransform* vt,
+        const char* fname);
+
+/* Dynamic casts of vector transforms to their concrete types,
+ * returning NULL if the transform is not of that type. */
+
+FaissRandomRotationMatrix* faiss_RandomRotationMatrix_cast(
+        FaissVectorTransform* vt);
+FaissPCAMatrix* faiss_PCAMatrix_cast(FaissVectorTransform* vt);
+FaissITQMatrix* faiss_ITQMatrix_cast(FaissVectorTransform* vt);
+FaissITQTransform* faiss_ITQTransform_cast(FaissVectorTransform* vt);
+FaissOPQMatrix* faiss_OPQMatrix_cast(FaissVectorTransform* vt);
+FaissRemapDimensionsTransform* faiss_RemapDimensionsTransform_cast(
+        FaissVectorTransform* vt);
+FaissNormalizationTransform* faiss_NormalizationTransform_cast(
+        FaissVectorTransform* vt);
+FaissCenteringTransform* faiss_CenteringTransform_cast(
+        FaissVectorTransform* vt);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif
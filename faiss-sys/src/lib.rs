@@ -12,6 +12,9 @@ mod bindings;
 #[cfg(not(feature = "gpu"))]
 pub use bindings::*;
 
+mod shim;
+pub use shim::*;
+
 #[cfg(test)]
 mod tests {
     use super::*;
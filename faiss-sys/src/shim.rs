@@ -0,0 +1,72 @@
+//! Declarations of the extensions to the Faiss C API in `shim/`,
+//! which are compiled by the build script.
+//!
+//! Unlike the other bindings, these are not generated,
+//! and need to be kept in sync with `shim/faiss_rs_shim.h`.
+
+use crate::{
+    FaissCenteringTransform, FaissDistanceComputer, FaissITQMatrix, FaissITQTransform, FaissIndex,
+    FaissNormalizationTransform, FaissOPQMatrix, FaissPCAMatrix, FaissRandomRotationMatrix,
+    FaissRemapDimensionsTransform, FaissVectorTransform,
+};
+
+#[repr(C)]
+#[derive(Debug, Copy, Clone)]
+pub struct FaissIDSelectorBitmap_H {
+    _unused: [u8; 0],
+}
+pub type FaissIDSelectorBitmap = FaissIDSelectorBitmap_H;
+unsafe extern "C" {
+    #[doc = " Create a selector admitting the IDs whose bit is set in the given bitmap.\n\n ID `i` is selected iff `i / 8 < n` and bit number `i % 8` of\n `bitmap[i / 8]` is set. The bitmap is not copied, so it must outlive the\n selector."]
+    pub fn faiss_IDSelectorBitmap_new(
+        p_sel: *mut *mut FaissIDSelectorBitmap,
+        n: usize,
+        bitmap: *const u8,
+    ) -> ::std::os::raw::c_int;
+}
+unsafe extern "C" {
+    #[doc = " Create a distance computer over the vectors stored in an index.\n This is equivalent to `faiss::Index::get_distance_computer`, which not all\n index types implement. The distance computer must be freed with\n `faiss_DistanceComputer_free`, and it must not outlive the index."]
+    pub fn faiss_Index_get_distance_computer(
+        index: *const FaissIndex,
+        p_dc: *mut *mut FaissDistanceComputer,
+    ) -> ::std::os::raw::c_int;
+}
+unsafe extern "C" {
+    #[doc = " Write a vector transform to a file.\n This is equivalent to `faiss::write_VectorTransform` when a file path is\n given."]
+    pub fn faiss_write_VectorTransform_fname(
+        vt: *const FaissVectorTransform,
+        fname: *const ::std::os::raw::c_char,
+    ) -> ::std::os::raw::c_int;
+}
+unsafe extern "C" {
+    pub fn faiss_RandomRotationMatrix_cast(
+        vt: *mut FaissVectorTransform,
+    ) -> *mut FaissRandomRotationMatrix;
+}
+unsafe extern "C" {
+    pub fn faiss_PCAMatrix_cast(vt: *mut FaissVectorTransform) -> *mut FaissPCAMatrix;
+}
+unsafe extern "C" {
+    pub fn faiss_ITQMatrix_cast(vt: *mut FaissVectorTransform) -> *mut FaissITQMatrix;
+}
+unsafe extern "C" {
+    pub fn faiss_ITQTransform_cast(vt: *mut FaissVectorTransform) -> *mut FaissITQTransform;
+}
+unsafe extern "C" {
+    pub fn faiss_OPQMatrix_cast(vt: *mut FaissVectorTransform) -> *mut FaissOPQMatrix;
+}
+unsafe extern "C" {
+    pub fn faiss_RemapDimensionsTransform_cast(
+        vt: *mut FaissVectorTransform,
+    ) -> *mut FaissRemapDimensionsTransform;
+}
+unsafe extern "C" {
+    pub fn faiss_NormalizationTransform_cast(
+        vt: *mut FaissVectorTransform,
+    ) -> *mut FaissNormalizationTransform;
+}
+unsafe extern "C" {
+    pub fn faiss_CenteringTransform_cast(
+        vt: *mut FaissVectorTransform,
+    ) -> *mut FaissCenteringTransform;
+}
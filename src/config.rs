@@ -0,0 +1,235 @@
+//! Global tuning parameters of the distance computations.
+//!
+//! These parameters are shared by all indexes in the process. Changing them
+//! while searches are running on other threads is not recommended, so they
+//! should be set at startup, or while no search is in progress.
+//!
+//! # Examples
+//!
+//! ```
+//! use faiss::config::{self, Config};
+//! # fn run() -> Result<(), Box<dyn std::error::Error>> {
+//!
+//! let default_threshold = config::blas_threshold();
+//! {
+//!     let _guard = Config {
+//!         blas_threshold: 64,
+//!         ..Config::current()
+//!     }
+//!     .scoped()?;
+//!     assert_eq!(config::blas_threshold(), 64);
+//! }
+//! // the previous configuration is restored when the guard is dropped
+//! assert_eq!(config::blas_threshold(), default_threshold);
+//! # Ok(())
+//! # }
+//! # run().unwrap();
+//! ```
+
+use crate::error::{Error, Result};
+use faiss_sys::*;
+use std::convert::TryFrom;
+use std::os::raw::c_int;
+
+fn to_c_int(value: u32) -> Result<c_int> {
+    c_int::try_from(value).map_err(|_| Error::ParameterValue)
+}
+
+fn to_block_size(value: u32) -> Result<c_int> {
+    if value == 0 {
+        return Err(Error::ParameterValue);
+    }
+    to_c_int(value)
+}
+
+/// Get the number of query vectors above which BLAS is used
+/// to compute distances in exhaustive searches.
+pub fn blas_threshold() -> u32 {
+    unsafe { faiss_get_distance_compute_blas_threshold() as u32 }
+}
+
+/// Set the number of query vectors above which BLAS is used
+/// to compute distances in exhaustive searches.
+///
+/// Fails with `Error::ParameterValue` if `value` is above `i32::MAX`.
+pub fn set_blas_threshold(value: u32) -> Result<()> {
+    let value = to_c_int(value)?;
+    unsafe { faiss_set_distance_compute_blas_threshold(value) }
+    Ok(())
+}
+
+/// Get the number of query vectors processed per block
+/// in BLAS distance computations.
+pub fn blas_query_bs() -> u32 {
+    unsafe { faiss_get_distance_compute_blas_query_bs() as u32 }
+}
+
+/// Set the number of query vectors processed per block
+/// in BLAS distance computations.
+///
+/// Fails with `Error::ParameterValue` if `value` is zero
+/// or above `i32::MAX`.
+pub fn set_blas_query_bs(value: u32) -> Result<()> {
+    let value = to_block_size(value)?;
+    unsafe { faiss_set_distance_compute_blas_query_bs(value) }
+    Ok(())
+}
+
+/// Get the number of database vectors processed per block
+/// in BLAS distance computations.
+pub fn blas_database_bs() -> u32 {
+    unsafe { faiss_get_distance_compute_blas_database_bs() as u32 }
+}
+
+/// Set the number of database vectors processed per block
+/// in BLAS distance computations.
+///
+/// Fails with `Error::ParameterValue` if `value` is zero
+/// or above `i32::MAX`.
+pub fn set_blas_database_bs(value: u32) -> Result<()> {
+    let value = to_block_size(value)?;
+    unsafe { faiss_set_distance_compute_blas_database_bs(value) }
+    Ok(())
+}
+
+/// Get the number of results `k` from which a reservoir is used
+/// to collect the results of exhaustive searches, instead of a heap.
+pub fn min_k_reservoir() -> u32 {
+    unsafe { faiss_get_distance_compute_min_k_reservoir() as u32 }
+}
+
+/// Set the number of results `k` from which a reservoir is used
+/// to collect the results of exhaustive searches, instead of a heap.
+///
+/// Fails with `Error::ParameterValue` if `value` is above `i32::MAX`.
+pub fn set_min_k_reservoir(value: u32) -> Result<()> {
+    let value = to_c_int(value)?;
+    unsafe { faiss_set_distance_compute_min_k_reservoir(value) }
+    Ok(())
+}
+
+/// A full set of the global tuning parameters.
+#[derive(Debug, Copy, Clone, Eq, Hash, PartialEq)]
+pub struct Config {
+    /// See [`blas_threshold`](fn.blas_threshold.html)
+    pub blas_threshold: u32,
+    /// See [`blas_query_bs`](fn.blas_query_bs.html)
+    pub blas_query_bs: u32,
+    /// See [`blas_database_bs`](fn.blas_database_bs.html)
+    pub blas_database_bs: u32,
+    /// See [`min_k_reservoir`](fn.min_k_reservoir.html)
+    pub min_k_reservoir: u32,
+}
+
+impl Config {
+    /// Obtain the parameters currently in use.
+    pub fn current() -> Self {
+        Config {
+            blas_threshold: blas_threshold(),
+            blas_query_bs: blas_query_bs(),
+            blas_database_bs: blas_database_bs(),
+            min_k_reservoir: min_k_reservoir(),
+        }
+    }
+
+    /// Make these parameters the ones in use.
+    ///
+    /// Fails with `Error::ParameterValue` if any of the block sizes is zero,
+    /// or if any of the parameters is above `i32::MAX`,
+    /// in which case none of the parameters are changed.
+    pub fn apply(&self) -> Result<()> {
+        let blas_threshold = to_c_int(self.blas_threshold)?;
+        let blas_query_bs = to_block_size(self.blas_query_bs)?;
+        let blas_database_bs = to_block_size(self.blas_database_bs)?;
+        let min_k_reservoir = to_c_int(self.min_k_reservoir)?;
+        unsafe {
+            faiss_set_distance_compute_blas_threshold(blas_threshold);
+            faiss_set_distance_compute_blas_query_bs(blas_query_bs);
+            faiss_set_distance_compute_blas_database_bs(blas_database_bs);
+            faiss_set_distance_compute_min_k_reservoir(min_k_reservoir);
+        }
+        Ok(())
+    }
+
+    /// Make these parameters the ones in use until the returned guard
+    /// is dropped, at which point the previous parameters are restored.
+    ///
+    /// Fails with `Error::ParameterValue` if any of the block sizes is zero,
+    /// or if any of the parameters is above `i32::MAX`,
+    /// in which case none of the parameters are changed.
+    pub fn scoped(&self) -> Result<ConfigGuard> {
+        let previous = Config::current();
+        self.apply()?;
+        Ok(ConfigGuard { previous })
+    }
+}
+
+/// Guard restoring the previous global parameters when dropped.
+///
+/// See [`Config::scoped`](struct.Config.html#method.scoped).
+#[derive(Debug)]
+#[must_use = "the previous configuration is restored as soon as the guard is dropped"]
+pub struct ConfigGuard {
+    previous: Config,
+}
+
+impl ConfigGuard {
+    /// Obtain the parameters which will be restored.
+    pub fn previous(&self) -> &Config {
+        &self.previous
+    }
+}
+
+impl Drop for ConfigGuard {
+    fn drop(&mut self) {
+        // the previous parameters were in use, so they are valid
+        let _ = self.previous.apply();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn scoped_config_is_restored() {
+        let initial = Config::current();
+        let tuned = Config {
+            blas_threshold: initial.blas_threshold + 1,
+            blas_query_bs: initial.blas_query_bs + 1,
+            blas_database_bs: initial.blas_database_bs + 1,
+            min_k_reservoir: initial.min_k_reservoir + 1,
+        };
+        {
+            let guard = tuned.scoped().unwrap();
+            assert_eq!(guard.previous(), &initial);
+            assert_eq!(Config::current(), tuned);
+            assert_eq!(blas_query_bs(), initial.blas_query_bs + 1);
+        }
+        assert_eq!(Config::current(), initial);
+
+        // an invalid configuration is rejected before anything is changed
+        let invalid = Config {
+            blas_database_bs: 0,
+            ..tuned
+        };
+        assert_eq!(invalid.scoped().err(), Some(Error::ParameterValue));
+        let invalid = Config {
+            min_k_reservoir: u32::MAX,
+            ..tuned
+        };
+        assert_eq!(invalid.apply(), Err(Error::ParameterValue));
+        assert_eq!(Config::current(), initial);
+    }
+
+    #[test]
+    fn setters_reject_out_of_range() {
+        assert_eq!(set_blas_query_bs(0), Err(Error::ParameterValue));
+        assert_eq!(set_blas_database_bs(0), Err(Error::ParameterValue));
+        assert_eq!(
+            set_blas_threshold(i32::MAX as u32 + 1),
+            Err(Error::ParameterValue)
+        );
+        assert_eq!(set_min_k_reservoir(u32::MAX), Err(Error::ParameterValue));
+    }
+}
@@ -0,0 +1,239 @@
+//! Distance computation utilities.
+//!
+//! These functions expose the distance kernels used internally by Faiss,
+//! which make use of SIMD instructions and BLAS where appropriate. Sets of
+//! vectors are given as contiguous slices of `d`-dimensional vectors. Each
+//! function allocating its output has an `_into` counterpart which writes to
+//! a buffer given by the caller instead.
+//!
+//! # Examples
+//!
+//! ```
+//! use faiss::distances::{l2sqr_ny, pairwise_l2sqr};
+//! # fn run() -> Result<(), Box<dyn std::error::Error>> {
+//! let queries = [0., 0., 1., 1.];
+//! let candidates = [0., 1., 2., 2., 1., 1.];
+//!
+//! let dis = pairwise_l2sqr(2, &queries, &candidates)?;
+//! assert_eq!(dis, vec![1., 8., 2., 1., 2., 0.]);
+//!
+//! let dis = l2sqr_ny(&queries[2..], &candidates)?;
+//! assert_eq!(dis, vec![1., 2., 0.]);
+//! # Ok(())
+//! # }
+//! # run().unwrap();
+//! ```
+
+use crate::error::{Error, Result};
+use faiss_sys::*;
+
+/// Obtain the number of `d`-dimensional vectors in `x`.
+fn num_vectors(d: usize, x: &[f32]) -> Result<usize> {
+    if d == 0 || x.len() % d != 0 {
+        return Err(Error::BadDimension);
+    }
+    Ok(x.len() / d)
+}
+
+/// Compute the squared L2 distances between all pairs of vectors of `xq`
+/// and `xb`, as a row-major matrix of `nq` rows and `nb` columns.
+///
+/// Fails with `Error::BadDimension` if the length of either set
+/// is not a multiple of `d`.
+pub fn pairwise_l2sqr(d: usize, xq: &[f32], xb: &[f32]) -> Result<Vec<f32>> {
+    let nq = num_vectors(d, xq)?;
+    let nb = num_vectors(d, xb)?;
+    let mut dis = vec![0.; nq * nb];
+    pairwise_l2sqr_into(d, xq, xb, &mut dis)?;
+    Ok(dis)
+}
+
+/// Compute the squared L2 distances between all pairs of vectors of `xq`
+/// and `xb` into `dis`, as a row-major matrix of `nq` rows and `nb` columns.
+///
+/// Fails with `Error::BadDimension` if the length of either set
+/// is not a multiple of `d`, or if `dis` is not of length `nq * nb`.
+pub fn pairwise_l2sqr_into(d: usize, xq: &[f32], xb: &[f32], dis: &mut [f32]) -> Result<()> {
+    let nq = num_vectors(d, xq)?;
+    let nb = num_vectors(d, xb)?;
+    if dis.len() != nq * nb {
+        return Err(Error::BadDimension);
+    }
+    if !dis.is_empty() {
+        unsafe {
+            faiss_pairwise_L2sqr_with_defaults(
+                d as i64,
+                nq as i64,
+                xq.as_ptr(),
+                nb as i64,
+                xb.as_ptr(),
+                dis.as_mut_ptr(),
+            );
+        }
+    }
+    Ok(())
+}
+
+/// Compute the inner products between the vector `x` and each vector of
+/// `ys`, which have the same dimensionality as `x`.
+///
+/// Fails with `Error::BadDimension` if the length of `ys`
+/// is not a multiple of the length of `x`.
+pub fn inner_products_ny(x: &[f32], ys: &[f32]) -> Result<Vec<f32>> {
+    let ny = num_vectors(x.len(), ys)?;
+    let mut ip = vec![0.; ny];
+    inner_products_ny_into(x, ys, &mut ip)?;
+    Ok(ip)
+}
+
+/// Compute the inner products between the vector `x` and each vector of
+/// `ys` into `ip`.
+///
+/// Fails with `Error::BadDimension` if the length of `ys` is not a multiple
+/// of the length of `x`, or if `ip` does not have one entry per vector.
+pub fn inner_products_ny_into(x: &[f32], ys: &[f32], ip: &mut [f32]) -> Result<()> {
+    let ny = num_vectors(x.len(), ys)?;
+    if ip.len() != ny {
+        return Err(Error::BadDimension);
+    }
+    unsafe {
+        faiss_fvec_inner_products_ny(ip.as_mut_ptr(), x.as_ptr(), ys.as_ptr(), x.len(), ny);
+    }
+    Ok(())
+}
+
+/// Compute the squared L2 distances between the vector `x` and each vector
+/// of `ys`, which have the same dimensionality as `x`.
+///
+/// Fails with `Error::BadDimension` if the length of `ys`
+/// is not a multiple of the length of `x`.
+pub fn l2sqr_ny(x: &[f32], ys: &[f32]) -> Result<Vec<f32>> {
+    let ny = num_vectors(x.len(), ys)?;
+    let mut dis = vec![0.; ny];
+    l2sqr_ny_into(x, ys, &mut dis)?;
+    Ok(dis)
+}
+
+/// Compute the squared L2 distances between the vector `x` and each vector
+/// of `ys` into `dis`.
+///
+/// Fails with `Error::BadDimension` if the length of `ys` is not a multiple
+/// of the length of `x`, or if `dis` does not have one entry per vector.
+pub fn l2sqr_ny_into(x: &[f32], ys: &[f32], dis: &mut [f32]) -> Result<()> {
+    let ny = num_vectors(x.len(), ys)?;
+    if dis.len() != ny {
+        return Err(Error::BadDimension);
+    }
+    unsafe {
+        faiss_fvec_L2sqr_ny(dis.as_mut_ptr(), x.as_ptr(), ys.as_ptr(), x.len(), ny);
+    }
+    Ok(())
+}
+
+/// Compute the squared L2 norm of a vector.
+pub fn norm_l2sqr(x: &[f32]) -> f32 {
+    unsafe { faiss_fvec_norm_L2sqr(x.as_ptr(), x.len()) }
+}
+
+/// Compute the L2 norms of a set of `d`-dimensional vectors.
+///
+/// Fails with `Error::BadDimension` if the length of `x`
+/// is not a multiple of `d`.
+pub fn norms_l2(d: usize, x: &[f32]) -> Result<Vec<f32>> {
+    let nx = num_vectors(d, x)?;
+    let mut norms = vec![0.; nx];
+    norms_l2_into(d, x, &mut norms)?;
+    Ok(norms)
+}
+
+/// Compute the L2 norms of a set of `d`-dimensional vectors into `norms`.
+///
+/// Fails with `Error::BadDimension` if the length of `x` is not a multiple
+/// of `d`, or if `norms` does not have one entry per vector.
+pub fn norms_l2_into(d: usize, x: &[f32], norms: &mut [f32]) -> Result<()> {
+    let nx = num_vectors(d, x)?;
+    if norms.len() != nx {
+        return Err(Error::BadDimension);
+    }
+    unsafe {
+        faiss_fvec_norms_L2(norms.as_mut_ptr(), x.as_ptr(), d, nx);
+    }
+    Ok(())
+}
+
+/// Compute the squared L2 norms of a set of `d`-dimensional vectors.
+///
+/// Fails with `Error::BadDimension` if the length of `x`
+/// is not a multiple of `d`.
+pub fn norms_l2sqr(d: usize, x: &[f32]) -> Result<Vec<f32>> {
+    let nx = num_vectors(d, x)?;
+    let mut norms = vec![0.; nx];
+    norms_l2sqr_into(d, x, &mut norms)?;
+    Ok(norms)
+}
+
+/// Compute the squared L2 norms of a set of `d`-dimensional vectors
+/// into `norms`.
+///
+/// Fails with `Error::BadDimension` if the length of `x` is not a multiple
+/// of `d`, or if `norms` does not have one entry per vector.
+pub fn norms_l2sqr_into(d: usize, x: &[f32], norms: &mut [f32]) -> Result<()> {
+    let nx = num_vectors(d, x)?;
+    if norms.len() != nx {
+        return Err(Error::BadDimension);
+    }
+    unsafe {
+        faiss_fvec_norms_L2sqr(norms.as_mut_ptr(), x.as_ptr(), d, nx);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const D: usize = 4;
+
+    #[test]
+    fn one_to_many() {
+        let x = [1., 0., 0., 1.];
+        let ys = [1., 0., 0., 1., 0., 2., 0., 0., -1., 1., 1., 1.];
+        assert_eq!(inner_products_ny(&x, &ys).unwrap(), vec![2., 0., 0.]);
+        assert_eq!(l2sqr_ny(&x, &ys).unwrap(), vec![0., 6., 6.]);
+
+        let mut dis = [0.; 3];
+        l2sqr_ny_into(&x, &ys, &mut dis).unwrap();
+        assert_eq!(dis, [0., 6., 6.]);
+        assert_eq!(l2sqr_ny(&x, &ys[1..]), Err(Error::BadDimension));
+        assert_eq!(
+            inner_products_ny_into(&x, &ys, &mut [0.; 2]),
+            Err(Error::BadDimension)
+        );
+    }
+
+    #[test]
+    fn pairwise() {
+        let xq = [1., 0., 0., 1., 0., 0., 0., 0.];
+        let xb = [1., 0., 0., 1., 0., 2., 0., 0., -1., 1., 1., 1.];
+        let dis = pairwise_l2sqr(D, &xq, &xb).unwrap();
+        assert_eq!(dis, vec![0., 6., 6., 2., 4., 4.]);
+        assert_eq!(
+            pairwise_l2sqr_into(D, &xq, &xb, &mut [0.; 5]),
+            Err(Error::BadDimension)
+        );
+        assert_eq!(pairwise_l2sqr(0, &xq, &xb), Err(Error::BadDimension));
+    }
+
+    #[test]
+    fn norms() {
+        let x = [3., 0., 0., 4., 0., 0., 0., 0.];
+        assert_eq!(norm_l2sqr(&x[..D]), 25.);
+        assert_eq!(norms_l2(D, &x).unwrap(), vec![5., 0.]);
+        assert_eq!(norms_l2sqr(D, &x).unwrap(), vec![25., 0.]);
+
+        let mut norms = [0.; 2];
+        norms_l2sqr_into(D, &x, &mut norms).unwrap();
+        assert_eq!(norms, [25., 0.]);
+        assert_eq!(norms_l2(3, &x), Err(Error::BadDimension));
+    }
+}
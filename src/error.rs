@@ -1,10 +1,13 @@
 //! Error handling module
 
+use crate::version::Version;
 use faiss_sys::*;
 use std::error::Error as StdError;
 use std::ffi::CStr;
 use std::fmt;
+use std::io;
 use std::os::raw::c_int;
+use std::sync::Arc;
 
 /// Type alias for results of functions in this crate.
 pub type Result<T> = ::std::result::Result<T, Error>;
@@ -26,6 +29,23 @@ pub enum Error {
     GpuResourcesMatch,
     /// The dimension of the vector does not match the dimension of the index
     BadDimension,
+    /// There is no sub-index (such as a shard or replica) at the given position
+    BadSubIndex,
+    /// The value of a parameter is out of its valid range
+    ParameterValue,
+    /// There is no stored vector with the given ID
+    BadId,
+    /// An operation required a query vector, but none was set
+    NoQuery,
+    /// The error came from a Rust I/O stream.
+    Io(IoError),
+    /// The linked Faiss library is not compatible with this crate.
+    IncompatibleVersion {
+        /// The version reported by the library
+        found: String,
+        /// The minimum version required
+        required: Version,
+    },
 }
 
 impl fmt::Display for Error {
@@ -40,16 +60,26 @@ impl fmt::Display for Error {
                 fmt.write_str("Number of GPU resources and devices do not match")
             }
             Error::BadDimension => fmt.write_str("invalid vector dimension"),
+            Error::BadSubIndex => fmt.write_str("invalid sub-index position"),
+            Error::ParameterValue => fmt.write_str("parameter value out of range"),
+            Error::BadId => fmt.write_str("no stored vector with the given ID"),
+            Error::NoQuery => fmt.write_str("no query vector was set"),
+            Error::Io(e) => write!(fmt, "I/O error: {}", e),
+            Error::IncompatibleVersion { found, required } => write!(
+                fmt,
+                "Incompatible Faiss library version {:?} (at least {} is required)",
+                found, required
+            ),
         }
     }
 }
 
 impl StdError for Error {
     fn source(&self) -> Option<&(dyn StdError + 'static)> {
-        if let Error::Native(err) = self {
-            Some(err)
-        } else {
-            None
+        match self {
+            Error::Native(err) => Some(err),
+            Error::Io(err) => Some(err.get_ref()),
+            _ => None,
         }
     }
 }
@@ -113,3 +143,47 @@ impl From<NativeError> for Error {
         Error::Native(e)
     }
 }
+
+/// An error derived from a Rust I/O stream,
+/// such as the reader or writer of an index.
+///
+/// Two I/O errors are equal if they are of the same kind
+/// and have the same message.
+#[derive(Debug, Clone)]
+pub struct IoError(Arc<io::Error>);
+
+impl IoError {
+    /// Getter for the kind of the I/O error.
+    pub fn kind(&self) -> io::ErrorKind {
+        self.0.kind()
+    }
+
+    /// Getter for the underlying I/O error.
+    pub fn get_ref(&self) -> &io::Error {
+        &self.0
+    }
+}
+
+impl PartialEq for IoError {
+    fn eq(&self, other: &Self) -> bool {
+        self.kind() == other.kind() && self.0.to_string() == other.0.to_string()
+    }
+}
+
+impl fmt::Display for IoError {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        self.0.fmt(fmt)
+    }
+}
+
+impl StdError for IoError {
+    fn source(&self) -> Option<&(dyn StdError + 'static)> {
+        Some(self.get_ref())
+    }
+}
+
+impl From<io::Error> for Error {
+    fn from(e: io::Error) -> Self {
+        Error::Io(IoError(Arc::new(e)))
+    }
+}
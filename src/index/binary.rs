@@ -0,0 +1,568 @@
+//! Binary index interface and native implementations.
+//!
+//! Binary indexes store vectors as packed bit codes, where each vector of
+//! dimension `d` (in bits) occupies `d / 8` bytes. Search is performed with
+//! the Hamming distance. Binary indexes can be created with
+//! [`binary_index_factory`], using the same description syntax as the native
+//! Faiss interface (e.g. `"BFlat"`, `"BIVF32"` or `"BHNSW16"`).
+//!
+//! [`binary_index_factory`]: fn.binary_index_factory.html
+//!
+//! # Examples
+//!
+//! ```no_run
+//! use faiss::index::binary::{binary_index_factory, BinaryIndex};
+//! # use faiss::error::Result;
+//! # fn run() -> Result<()> {
+//! let mut index = binary_index_factory(256, "BFlat")?;
+//! # let my_codes: &[u8] = unimplemented!();
+//! index.add(my_codes)?;
+//! # let my_query: &[u8] = unimplemented!();
+//! let result = index.search(my_query, 5)?;
+//! for (l, d) in result.labels.iter().zip(result.distances.iter()) {
+//!     println!("{} (Hamming distance = {})", l, d);
+//! }
+//! # Ok(())
+//! # }
+//! # run().unwrap()
+//! ```
+
+use crate::error::{Error, Result};
+use crate::faiss_try;
+use crate::index::{AssignSearchResult, Idx, RangeSearchResult, TryClone};
+use crate::metric::MetricType;
+use crate::selector::IdSelector;
+use faiss_sys::*;
+use std::ffi::CString;
+use std::ptr;
+
+/// The outcome of a binary index search operation.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BinarySearchResult {
+    pub distances: Vec<i32>,
+    pub labels: Vec<Idx>,
+}
+
+/// Interface for a Faiss binary index. Vectors are given and retrieved as
+/// contiguous slices of packed bit codes, `d / 8` bytes per vector.
+///
+/// As with [`Index`], not all operations are supported by every binary
+/// index implementation.
+///
+/// [`Index`]: ../trait.Index.html
+pub trait BinaryIndex {
+    /// Whether the Index does not require training, or if training is done already
+    fn is_trained(&self) -> bool;
+
+    /// The total number of vectors indexed
+    fn ntotal(&self) -> u64;
+
+    /// The dimensionality of the indexed vectors, in bits
+    fn d(&self) -> u32;
+
+    /// The size of each vector code in bytes
+    fn code_size(&self) -> usize {
+        self.d() as usize / 8
+    }
+
+    /// The metric type assumed by the index
+    fn metric_type(&self) -> MetricType;
+
+    /// Add new data vectors to the index.
+    /// This assumes a C-contiguous memory slice of vector codes, where the
+    /// total number of vectors is `x.len() / code_size`.
+    fn add(&mut self, x: &[u8]) -> Result<()>;
+
+    /// Add new data vectors to the index with IDs.
+    /// This assumes a C-contiguous memory slice of vector codes, where the
+    /// total number of vectors is `x.len() / code_size`,
+    /// with one ID per vector.
+    /// Not all index types may support this operation.
+    fn add_with_ids(&mut self, x: &[u8], xids: &[Idx]) -> Result<()>;
+
+    /// Train the underlying index with the given data.
+    fn train(&mut self, x: &[u8]) -> Result<()>;
+
+    /// Similar to `search`, but only provides the labels.
+    fn assign(&mut self, q: &[u8], k: usize) -> Result<AssignSearchResult>;
+
+    /// Perform a search for the `k` closest vectors to the given query vectors.
+    fn search(&mut self, q: &[u8], k: usize) -> Result<BinarySearchResult>;
+
+    /// Perform a ranged search for the vectors closest to the given query vectors
+    /// by the given Hamming radius.
+    fn range_search(&mut self, q: &[u8], radius: i32) -> Result<RangeSearchResult>;
+
+    /// Reconstruct the stored vector code with the given key into `output`,
+    /// which must be `code_size` bytes long.
+    fn reconstruct(&self, key: Idx, output: &mut [u8]) -> Result<()>;
+
+    /// Reconstruct `count` consecutive vector codes, starting from
+    /// `first_key`, into `output`.
+    fn reconstruct_n(&self, first_key: Idx, count: usize, output: &mut [u8]) -> Result<()>;
+
+    /// Clear the entire index.
+    fn reset(&mut self) -> Result<()>;
+
+    /// Remove data vectors represented by IDs.
+    fn remove_ids(&mut self, sel: &IdSelector) -> Result<usize>;
+
+    /// Index verbosity level
+    fn verbose(&self) -> bool;
+
+    /// Set Index verbosity level
+    fn set_verbose(&mut self, value: bool);
+}
+
+impl<I> BinaryIndex for Box<I>
+where
+    I: BinaryIndex,
+{
+    fn is_trained(&self) -> bool {
+        (**self).is_trained()
+    }
+
+    fn ntotal(&self) -> u64 {
+        (**self).ntotal()
+    }
+
+    fn d(&self) -> u32 {
+        (**self).d()
+    }
+
+    fn code_size(&self) -> usize {
+        (**self).code_size()
+    }
+
+    fn metric_type(&self) -> MetricType {
+        (**self).metric_type()
+    }
+
+    fn add(&mut self, x: &[u8]) -> Result<()> {
+        (**self).add(x)
+    }
+
+    fn add_with_ids(&mut self, x: &[u8], xids: &[Idx]) -> Result<()> {
+        (**self).add_with_ids(x, xids)
+    }
+
+    fn train(&mut self, x: &[u8]) -> Result<()> {
+        (**self).train(x)
+    }
+
+    fn assign(&mut self, q: &[u8], k: usize) -> Result<AssignSearchResult> {
+        (**self).assign(q, k)
+    }
+
+    fn search(&mut self, q: &[u8], k: usize) -> Result<BinarySearchResult> {
+        (**self).search(q, k)
+    }
+
+    fn range_search(&mut self, q: &[u8], radius: i32) -> Result<RangeSearchResult> {
+        (**self).range_search(q, radius)
+    }
+
+    fn reconstruct(&self, key: Idx, output: &mut [u8]) -> Result<()> {
+        (**self).reconstruct(key, output)
+    }
+
+    fn reconstruct_n(&self, first_key: Idx, count: usize, output: &mut [u8]) -> Result<()> {
+        (**self).reconstruct_n(first_key, count, output)
+    }
+
+    fn reset(&mut self) -> Result<()> {
+        (**self).reset()
+    }
+
+    fn remove_ids(&mut self, sel: &IdSelector) -> Result<usize> {
+        (**self).remove_ids(sel)
+    }
+
+    fn verbose(&self) -> bool {
+        (**self).verbose()
+    }
+
+    fn set_verbose(&mut self, value: bool) {
+        (**self).set_verbose(value)
+    }
+}
+
+/// Sub-trait for native implementations of a Faiss binary index.
+pub trait NativeBinaryIndex: BinaryIndex {
+    /// Retrieve a pointer to the native binary index object.
+    fn inner_ptr(&self) -> *mut FaissIndexBinary;
+}
+
+impl<NI: NativeBinaryIndex> NativeBinaryIndex for Box<NI> {
+    fn inner_ptr(&self) -> *mut FaissIndexBinary {
+        (**self).inner_ptr()
+    }
+}
+
+/// Native implementation of a Faiss binary index
+/// running on the CPU.
+#[derive(Debug)]
+pub struct BinaryIndexImpl {
+    inner: *mut FaissIndexBinary,
+}
+
+unsafe impl Send for BinaryIndexImpl {}
+unsafe impl Sync for BinaryIndexImpl {}
+
+impl Drop for BinaryIndexImpl {
+    fn drop(&mut self) {
+        unsafe {
+            faiss_IndexBinary_free(self.inner);
+        }
+    }
+}
+
+impl BinaryIndexImpl {
+    /// Obtain the number of vector codes in `x`,
+    /// failing if its length is not a multiple of the code size.
+    fn num_codes(&self, x: &[u8]) -> Result<usize> {
+        let code_size = self.code_size();
+        if code_size == 0 || x.len() % code_size != 0 {
+            return Err(Error::BadDimension);
+        }
+        Ok(x.len() / code_size)
+    }
+
+    /// Create a binary index using the given pointer to a native object.
+    ///
+    /// # Safety
+    ///
+    /// `inner_ptr` must point to a valid, non-freed CPU binary index,
+    /// and cannot be shared across multiple instances.
+    pub unsafe fn from_inner_ptr(inner_ptr: *mut FaissIndexBinary) -> Self {
+        BinaryIndexImpl { inner: inner_ptr }
+    }
+}
+
+impl TryClone for BinaryIndexImpl {
+    fn try_clone(&self) -> Result<Self>
+    where
+        Self: Sized,
+    {
+        unsafe {
+            let mut new_index_ptr = ptr::null_mut();
+            faiss_try(faiss_clone_index_binary(self.inner, &mut new_index_ptr))?;
+            Ok(BinaryIndexImpl {
+                inner: new_index_ptr,
+            })
+        }
+    }
+}
+
+impl NativeBinaryIndex for BinaryIndexImpl {
+    fn inner_ptr(&self) -> *mut FaissIndexBinary {
+        self.inner
+    }
+}
+
+impl BinaryIndex for BinaryIndexImpl {
+    fn is_trained(&self) -> bool {
+        unsafe { faiss_IndexBinary_is_trained(self.inner) != 0 }
+    }
+
+    fn ntotal(&self) -> u64 {
+        unsafe { faiss_IndexBinary_ntotal(self.inner) as u64 }
+    }
+
+    fn d(&self) -> u32 {
+        unsafe { faiss_IndexBinary_d(self.inner) as u32 }
+    }
+
+    fn metric_type(&self) -> MetricType {
+        unsafe { MetricType::from_code(faiss_IndexBinary_metric_type(self.inner) as u32).unwrap() }
+    }
+
+    fn add(&mut self, x: &[u8]) -> Result<()> {
+        unsafe {
+            let n = self.num_codes(x)?;
+            faiss_try(faiss_IndexBinary_add(self.inner, n as idx_t, x.as_ptr()))?;
+            Ok(())
+        }
+    }
+
+    fn add_with_ids(&mut self, x: &[u8], xids: &[Idx]) -> Result<()> {
+        unsafe {
+            let n = self.num_codes(x)?;
+            if xids.len() != n {
+                return Err(Error::BadDimension);
+            }
+            faiss_try(faiss_IndexBinary_add_with_ids(
+                self.inner,
+                n as idx_t,
+                x.as_ptr(),
+                xids.as_ptr() as *const _,
+            ))?;
+            Ok(())
+        }
+    }
+
+    fn train(&mut self, x: &[u8]) -> Result<()> {
+        unsafe {
+            let n = self.num_codes(x)?;
+            faiss_try(faiss_IndexBinary_train(self.inner, n as idx_t, x.as_ptr()))?;
+            Ok(())
+        }
+    }
+
+    fn assign(&mut self, query: &[u8], k: usize) -> Result<AssignSearchResult> {
+        unsafe {
+            let nq = self.num_codes(query)?;
+            let mut out_labels = vec![Idx::none(); k * nq];
+            faiss_try(faiss_IndexBinary_assign(
+                self.inner,
+                nq as idx_t,
+                query.as_ptr(),
+                out_labels.as_mut_ptr() as *mut _,
+                k as idx_t,
+            ))?;
+            Ok(AssignSearchResult { labels: out_labels })
+        }
+    }
+
+    fn search(&mut self, query: &[u8], k: usize) -> Result<BinarySearchResult> {
+        unsafe {
+            let nq = self.num_codes(query)?;
+            let mut distances = vec![0_i32; k * nq];
+            let mut labels = vec![Idx::none(); k * nq];
+            faiss_try(faiss_IndexBinary_search(
+                self.inner,
+                nq as idx_t,
+                query.as_ptr(),
+                k as idx_t,
+                distances.as_mut_ptr(),
+                labels.as_mut_ptr() as *mut _,
+            ))?;
+            Ok(BinarySearchResult { distances, labels })
+        }
+    }
+
+    fn range_search(&mut self, query: &[u8], radius: i32) -> Result<RangeSearchResult> {
+        unsafe {
+            let nq = self.num_codes(query)? as idx_t;
+            let mut p_res: *mut FaissRangeSearchResult = ptr::null_mut();
+            faiss_try(faiss_RangeSearchResult_new(&mut p_res, nq))?;
+            // take ownership right away, so that the result is freed on error
+            let res = RangeSearchResult { inner: p_res };
+            faiss_try(faiss_IndexBinary_range_search(
+                self.inner,
+                nq,
+                query.as_ptr(),
+                radius,
+                p_res,
+            ))?;
+            Ok(res)
+        }
+    }
+
+    fn reconstruct(&self, key: Idx, output: &mut [u8]) -> Result<()> {
+        unsafe {
+            if self.code_size() != output.len() {
+                return Err(Error::BadDimension);
+            }
+
+            faiss_try(faiss_IndexBinary_reconstruct(
+                self.inner,
+                key.to_native(),
+                output.as_mut_ptr(),
+            ))?;
+            Ok(())
+        }
+    }
+
+    fn reconstruct_n(&self, first_key: Idx, count: usize, output: &mut [u8]) -> Result<()> {
+        unsafe {
+            if count * self.code_size() != output.len() {
+                return Err(Error::BadDimension);
+            }
+
+            faiss_try(faiss_IndexBinary_reconstruct_n(
+                self.inner,
+                first_key.to_native(),
+                count as idx_t,
+                output.as_mut_ptr(),
+            ))?;
+            Ok(())
+        }
+    }
+
+    fn reset(&mut self) -> Result<()> {
+        unsafe {
+            faiss_try(faiss_IndexBinary_reset(self.inner))?;
+            Ok(())
+        }
+    }
+
+    fn remove_ids(&mut self, sel: &IdSelector) -> Result<usize> {
+        unsafe {
+            let mut n_removed = 0;
+            faiss_try(faiss_IndexBinary_remove_ids(
+                self.inner,
+                sel.inner_ptr(),
+                &mut n_removed,
+            ))?;
+            Ok(n_removed)
+        }
+    }
+
+    fn verbose(&self) -> bool {
+        unsafe { faiss_IndexBinary_verbose(self.inner) != 0 }
+    }
+
+    fn set_verbose(&mut self, value: bool) {
+        unsafe {
+            faiss_IndexBinary_set_verbose(self.inner, std::os::raw::c_int::from(value));
+        }
+    }
+}
+
+/// Use the binary index factory to create a native instance of a Faiss
+/// binary index, for `d`-dimensional vectors (in bits, multiple of 8).
+/// `description` should follow the exact guidelines as the native Faiss
+/// interface (see the [Faiss wiki](https://github.com/facebookresearch/faiss/wiki/Binary-indexes)
+/// for examples).
+///
+/// # Error
+///
+/// This function returns an error if the description contains any byte with the value `\0` (since
+/// it cannot be converted to a C string), or if the internal index factory operation fails.
+pub fn binary_index_factory<D>(d: u32, description: D) -> Result<BinaryIndexImpl>
+where
+    D: AsRef<str>,
+{
+    unsafe {
+        let description =
+            CString::new(description.as_ref()).map_err(|_| Error::IndexDescription)?;
+        let mut index_ptr = ptr::null_mut();
+        faiss_try(faiss_index_binary_factory(
+            &mut index_ptr,
+            (d & 0x7FFF_FFFF) as i32,
+            description.as_ptr(),
+        ))?;
+        Ok(BinaryIndexImpl { inner: index_ptr })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{binary_index_factory, BinaryIndex};
+    use crate::error::Error;
+    use crate::index::{Idx, TryClone};
+    use crate::selector::IdSelector;
+
+    const D: u32 = 32;
+
+    #[test]
+    fn binary_index_factory_flat() {
+        let index = binary_index_factory(D, "BFlat").unwrap();
+        assert!(index.is_trained());
+        assert_eq!(index.ntotal(), 0);
+        assert_eq!(index.d(), D);
+        assert_eq!(index.code_size(), 4);
+    }
+
+    #[test]
+    fn bad_binary_index_factory_description() {
+        let r = binary_index_factory(D, "fdnoyq");
+        assert!(r.is_err());
+        let r = binary_index_factory(D, "BFlat\0BFlat");
+        assert!(r.is_err());
+    }
+
+    #[test]
+    fn binary_flat_index_search() {
+        let mut index = binary_index_factory(D, "BFlat").unwrap();
+        let some_data = &[
+            0x00_u8, 0x00, 0x00, 0x00, 0xFF, 0xFF, 0xFF, 0xFF, 0x0F, 0x00, 0x00, 0x00, 0xFF, 0xFF,
+            0x00, 0x00,
+        ];
+        index.add(some_data).unwrap();
+        assert_eq!(index.ntotal(), 4);
+
+        let my_query = [0x01, 0x00, 0x00, 0x00];
+        let result = index.search(&my_query, 4).unwrap();
+        assert_eq!(
+            result.labels,
+            vec![0, 2, 3, 1]
+                .into_iter()
+                .map(Idx::new)
+                .collect::<Vec<_>>()
+        );
+        assert_eq!(result.distances, vec![1, 3, 15, 31]);
+
+        let result = index.assign(&my_query, 2).unwrap();
+        assert_eq!(result.labels, vec![Idx::new(0), Idx::new(2)]);
+
+        let result = index.range_search(&my_query, 4).unwrap();
+        let (distances, labels) = result.distance_and_labels();
+        assert_eq!(labels.len(), 2);
+        assert!(labels.contains(&Idx::new(0)) && labels.contains(&Idx::new(2)));
+        assert!(distances.iter().all(|x| *x < 4.));
+
+        index.reset().unwrap();
+        assert_eq!(index.ntotal(), 0);
+    }
+
+    #[test]
+    fn binary_flat_index_reconstruct_and_remove() {
+        let mut index = binary_index_factory(D, "BFlat").unwrap();
+        let some_data = &[
+            0x00_u8, 0x00, 0x00, 0x00, 0xFF, 0xFF, 0xFF, 0xFF, 0x0F, 0x00, 0x00, 0x00,
+        ];
+        index.add(some_data).unwrap();
+
+        let mut code = [0_u8; 4];
+        index.reconstruct(Idx::new(1), &mut code).unwrap();
+        assert_eq!(code, [0xFF; 4]);
+
+        let mut codes = [0_u8; 8];
+        index.reconstruct_n(Idx::new(1), 2, &mut codes).unwrap();
+        assert_eq!(&codes, &some_data[4..]);
+
+        let mut bad_code = [0_u8; 3];
+        assert!(index.reconstruct(Idx::new(1), &mut bad_code).is_err());
+
+        let sel = IdSelector::batch(&[Idx::new(1)]).unwrap();
+        let n_removed = index.remove_ids(&sel).unwrap();
+        assert_eq!(n_removed, 1);
+        assert_eq!(index.ntotal(), 2);
+    }
+
+    #[test]
+    fn binary_index_bad_lengths() {
+        let mut index = binary_index_factory(D, "BIVF1").unwrap();
+        let some_data = [0x0F_u8; 8];
+        assert_eq!(index.train(&some_data[..7]), Err(Error::BadDimension));
+        index.train(&some_data).unwrap();
+
+        assert_eq!(index.add(&some_data[..5]), Err(Error::BadDimension));
+        assert_eq!(
+            index.add_with_ids(&some_data, &[Idx::new(7)]),
+            Err(Error::BadDimension)
+        );
+        index
+            .add_with_ids(&some_data, &[Idx::new(7), Idx::new(8)])
+            .unwrap();
+        assert_eq!(index.ntotal(), 2);
+
+        let bad_query = [0x00_u8; 3];
+        assert_eq!(index.search(&bad_query, 1), Err(Error::BadDimension));
+        assert_eq!(index.assign(&bad_query, 1), Err(Error::BadDimension));
+        assert!(index.range_search(&bad_query, 1).is_err());
+    }
+
+    #[test]
+    fn binary_index_clone() {
+        let mut index = binary_index_factory(D, "BFlat").unwrap();
+        index.add(&[0xAB, 0xCD, 0xEF, 0x01]).unwrap();
+
+        let mut index2 = index.try_clone().unwrap();
+        index2.add(&[0x00, 0x00, 0x00, 0x00]).unwrap();
+        assert_eq!(index.ntotal(), 1);
+        assert_eq!(index2.ntotal(), 2);
+    }
+}
@@ -0,0 +1,104 @@
+//! Standalone codec interface.
+//!
+//! Some index types can be used as standalone codecs, which compress vectors
+//! into a fixed number of bytes and decompress them back into (approximate)
+//! vectors, without having to add them to the index. This makes it possible
+//! to keep the compressed vectors in an external storage.
+//!
+//! # Examples
+//!
+//! ```
+//! use faiss::{index_factory, Index, MetricType};
+//! use faiss::index::codec::Codec;
+//! # fn run() -> Result<(), Box<dyn std::error::Error>> {
+//! let mut index = index_factory(4, "SQ8", MetricType::L2)?;
+//! let data = [0., 1., 2., 3., 3., 2., 1., 0.];
+//! index.train(&data)?;
+//!
+//! let codes = index.encode(&data)?;
+//! assert_eq!(codes.len(), 2 * index.code_size()?);
+//! let decoded = index.decode(&codes)?;
+//! assert_eq!(decoded.len(), data.len());
+//! # Ok(())
+//! # }
+//! # run().unwrap();
+//! ```
+
+use crate::error::Result;
+use crate::index::Index;
+
+/// Trait for indexes which can be used as standalone codecs.
+///
+/// Encoding usually requires the index to be trained beforehand.
+pub trait Codec: Index {
+    /// Obtain the size in bytes of the code of a single vector.
+    fn code_size(&self) -> Result<usize>;
+
+    /// Encode a set of vectors, producing [`code_size`] bytes per vector.
+    ///
+    /// Fails with [`Error::BadDimension`] if the length of `x` is not a
+    /// multiple of the index dimensionality.
+    ///
+    /// [`code_size`]: #tymethod.code_size
+    /// [`Error::BadDimension`]: ../../error/enum.Error.html#variant.BadDimension
+    fn encode(&self, x: &[f32]) -> Result<Vec<u8>>;
+
+    /// Decode a set of codes back into vectors.
+    ///
+    /// Fails with [`Error::BadDimension`] if the length of `codes` is not a
+    /// multiple of the code size.
+    ///
+    /// [`Error::BadDimension`]: ../../error/enum.Error.html#variant.BadDimension
+    fn decode(&self, codes: &[u8]) -> Result<Vec<f32>>;
+}
+
+impl<C> Codec for Box<C>
+where
+    C: Codec,
+{
+    fn code_size(&self) -> Result<usize> {
+        (**self).code_size()
+    }
+
+    fn encode(&self, x: &[f32]) -> Result<Vec<u8>> {
+        (**self).encode(x)
+    }
+
+    fn decode(&self, codes: &[u8]) -> Result<Vec<f32>> {
+        (**self).decode(codes)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Codec;
+    use crate::error::Error;
+    use crate::index::{index_factory, Index};
+    use crate::metric::MetricType;
+
+    const D: u32 = 8;
+
+    #[test]
+    fn pq_codec_round_trip() {
+        let mut index = index_factory(D, "PQ4x8", MetricType::L2).unwrap();
+        let some_data: Vec<f32> = (0..D as usize * 256).map(|i| (i % 17) as f32).collect();
+        index.train(&some_data).unwrap();
+        assert_eq!(index.code_size().unwrap(), 4);
+
+        let x = &some_data[..2 * D as usize];
+        let codes = index.encode(x).unwrap();
+        assert_eq!(codes.len(), 8);
+        let decoded = index.decode(&codes).unwrap();
+        assert_eq!(decoded.len(), x.len());
+        // encoding does not add the vectors to the index
+        assert_eq!(index.ntotal(), 0);
+    }
+
+    #[test]
+    fn codec_bad_sizes() {
+        let index = index_factory(D, "Flat", MetricType::L2).unwrap();
+        assert_eq!(index.code_size().unwrap(), D as usize * 4);
+        assert_eq!(index.encode(&[0.; 7]), Err(Error::BadDimension));
+        assert_eq!(index.decode(&[0; 33]), Err(Error::BadDimension));
+    }
+}
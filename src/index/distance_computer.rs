@@ -0,0 +1,229 @@
+//! Distance computations over the vectors stored in an index.
+//!
+//! A [`DistanceComputer`] computes distances between a query and the vectors
+//! stored in an index, or between two stored vectors, by their IDs.
+//! The distances are computed directly from the stored codes, without
+//! reconstructing any vector. This is useful for implementing custom search
+//! and reranking algorithms. Only some index types, such as flat and scalar
+//! quantizer indexes, support distance computers.
+//!
+//! [`DistanceComputer`]: struct.DistanceComputer.html
+//!
+//! # Examples
+//!
+//! ```
+//! use faiss::{Idx, Index, FlatIndex};
+//! use faiss::index::distance_computer::DistanceComputer;
+//! # fn run() -> Result<(), Box<dyn std::error::Error>> {
+//! let mut index = FlatIndex::new_l2(2)?;
+//! index.add(&[0., 0., 1., 1., 2., 2.])?;
+//!
+//! let mut dc = DistanceComputer::new(&index)?;
+//! dc.set_query(&[0., 1.])?;
+//! assert_eq!(dc.query_distance(Idx::new(1))?, 1.);
+//! assert_eq!(dc.symmetric_distance(Idx::new(0), Idx::new(2))?, 8.);
+//! # Ok(())
+//! # }
+//! # run().unwrap();
+//! ```
+
+use crate::error::{Error, Result};
+use crate::faiss_try;
+use crate::index::{CpuIndex, Idx, Index, NativeIndex};
+use faiss_sys::*;
+
+use std::marker::PhantomData;
+use std::ptr;
+
+/// Distance computer borrowed from an index.
+///
+/// See the [module level documentation] for more information.
+///
+/// [module level documentation]: ./index.html
+#[derive(Debug)]
+pub struct DistanceComputer<'a> {
+    inner: *mut FaissDistanceComputer,
+    d: usize,
+    ntotal: u64,
+    // the native distance computer only keeps a pointer to the query
+    query: Vec<f32>,
+    phantom: PhantomData<&'a ()>,
+}
+
+impl Drop for DistanceComputer<'_> {
+    fn drop(&mut self) {
+        unsafe {
+            faiss_DistanceComputer_free(self.inner);
+        }
+    }
+}
+
+impl<'a> DistanceComputer<'a> {
+    /// Create a distance computer over the vectors stored in the given index.
+    ///
+    /// The index cannot be modified while the distance computer is alive.
+    /// Fails with a native error if the index type
+    /// does not support distance computers.
+    pub fn new<I>(index: &'a I) -> Result<Self>
+    where
+        I: NativeIndex + CpuIndex + Index,
+    {
+        unsafe {
+            let mut inner = ptr::null_mut();
+            faiss_try(faiss_Index_get_distance_computer(
+                index.inner_ptr(),
+                &mut inner,
+            ))?;
+            Ok(DistanceComputer {
+                inner,
+                d: index.d() as usize,
+                ntotal: index.ntotal(),
+                query: Vec::new(),
+                phantom: PhantomData,
+            })
+        }
+    }
+
+    /// Set the query vector of subsequent calls to [`query_distance`].
+    ///
+    /// Fails with `Error::BadDimension` if the length of the query
+    /// is not the index dimensionality.
+    ///
+    /// [`query_distance`]: #method.query_distance
+    pub fn set_query(&mut self, query: &[f32]) -> Result<()> {
+        if query.len() != self.d {
+            return Err(Error::BadDimension);
+        }
+        self.query.clear();
+        self.query.extend_from_slice(query);
+        unsafe {
+            faiss_try(faiss_DistanceComputer_set_query(
+                self.inner,
+                self.query.as_ptr(),
+            ))?;
+        }
+        Ok(())
+    }
+
+    /// Compute the distance between the query and the stored vector `i`.
+    ///
+    /// Fails with `Error::NoQuery` if no query was set,
+    /// or with `Error::BadId` if `i` is not the ID of a stored vector.
+    pub fn query_distance(&mut self, i: Idx) -> Result<f32> {
+        if self.query.is_empty() {
+            return Err(Error::NoQuery);
+        }
+        let i = self.check_id(i)?;
+        let mut dis = 0.;
+        unsafe {
+            faiss_try(faiss_DistanceComputer_vector_to_query_dis(
+                self.inner, i, &mut dis,
+            ))?;
+        }
+        Ok(dis)
+    }
+
+    /// Compute the distances between the query and each of the stored vectors
+    /// with the given IDs.
+    ///
+    /// Fails with `Error::NoQuery` if no query was set, or with `Error::BadId`
+    /// if any of the IDs is not the ID of a stored vector.
+    pub fn query_distances(&mut self, ids: &[Idx]) -> Result<Vec<f32>> {
+        ids.iter().map(|&i| self.query_distance(i)).collect()
+    }
+
+    /// Compute the distance between the stored vectors `i` and `j`.
+    ///
+    /// Fails with `Error::BadId` if `i` or `j`
+    /// is not the ID of a stored vector.
+    pub fn symmetric_distance(&mut self, i: Idx, j: Idx) -> Result<f32> {
+        let i = self.check_id(i)?;
+        let j = self.check_id(j)?;
+        let mut dis = 0.;
+        unsafe {
+            faiss_try(faiss_DistanceComputer_symmetric_dis(
+                self.inner, i, j, &mut dis,
+            ))?;
+        }
+        Ok(dis)
+    }
+
+    fn check_id(&self, i: Idx) -> Result<idx_t> {
+        match i.get() {
+            Some(i) if i < self.ntotal => Ok(i as idx_t),
+            _ => Err(Error::BadId),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::DistanceComputer;
+    use crate::error::Error;
+    use crate::index::{flat::FlatIndexImpl, index_factory, Idx, Index};
+    use crate::MetricType;
+
+    const D: u32 = 4;
+
+    #[test]
+    fn flat_distance_computer() {
+        let mut index = FlatIndexImpl::new_ip(D).unwrap();
+        index
+            .add(&[1., 0., 0., 0., 0., 1., 0., 0., 1., 1., 1., 1.])
+            .unwrap();
+
+        let mut dc = DistanceComputer::new(&index).unwrap();
+        assert_eq!(dc.query_distance(Idx::new(0)), Err(Error::NoQuery));
+        assert_eq!(dc.set_query(&[1., 2., 3.]), Err(Error::BadDimension));
+        dc.set_query(&[1., 2., 3., 4.]).unwrap();
+        assert_eq!(
+            dc.query_distances(&[Idx::new(0), Idx::new(1), Idx::new(2)]),
+            Ok(vec![1., 2., 10.])
+        );
+        assert_eq!(dc.symmetric_distance(Idx::new(1), Idx::new(2)), Ok(1.));
+    }
+
+    #[test]
+    fn sq_distance_computer() {
+        let mut index = index_factory(D, "SQ8", MetricType::L2).unwrap();
+        let some_data = [0., 0., 0., 0., 1., 1., 1., 1., 2., 2., 2., 2.];
+        index.train(&some_data).unwrap();
+        index.add(&some_data).unwrap();
+
+        let mut dc = DistanceComputer::new(&index).unwrap();
+        dc.set_query(&[2., 2., 2., 2.]).unwrap();
+        let dis = dc
+            .query_distances(&[Idx::new(0), Idx::new(1), Idx::new(2)])
+            .unwrap();
+        assert!(dis[0] > dis[1] && dis[1] > dis[2]);
+        assert!((dis[0] - 16.).abs() < 0.1);
+        let dis = dc.symmetric_distance(Idx::new(0), Idx::new(2)).unwrap();
+        assert!((dis - 16.).abs() < 0.1);
+    }
+
+    #[test]
+    fn distance_computer_bad_ids() {
+        let mut index = index_factory(D, "Flat", MetricType::L2).unwrap();
+        index.add(&[0., 0., 0., 0., 1., 1., 1., 1.]).unwrap();
+
+        let mut dc = DistanceComputer::new(&index).unwrap();
+        dc.set_query(&[2., 2., 2., 2.]).unwrap();
+        assert_eq!(dc.query_distance(Idx::new(1)), Ok(4.));
+        assert_eq!(dc.query_distance(Idx::new(2)), Err(Error::BadId));
+        assert_eq!(dc.query_distance(Idx::none()), Err(Error::BadId));
+        assert_eq!(
+            dc.symmetric_distance(Idx::new(0), Idx::new(1)),
+            Ok(D as f32)
+        );
+        assert_eq!(
+            dc.symmetric_distance(Idx::new(0), Idx::new(2)),
+            Err(Error::BadId)
+        );
+    }
+
+    #[test]
+    fn distance_computer_not_supported() {
+        let index = index_factory(D, "IVF1,Flat", MetricType::L2).unwrap();
+        assert!(DistanceComputer::new(&index).is_err());
+    }
+}
@@ -161,6 +161,8 @@ mod tests {
         UpcastIndex,
     };
     use crate::metric::MetricType;
+    use crate::search_params::SearchParameters;
+    use crate::selector::IdSelector;
 
     const D: u32 = 8;
 
@@ -286,6 +288,30 @@ mod tests {
         assert_eq!(index.ntotal(), 0);
     }
 
+    #[test]
+    fn flat_index_search_with_params() {
+        let mut index = FlatIndexImpl::new_l2(D).unwrap();
+        let some_data = &[
+            7.5_f32, -7.5, 7.5, -7.5, 7.5, 7.5, 7.5, 7.5, -1., 1., 1., 1., 1., 1., 1., -1., 0., 0.,
+            0., 1., 1., 0., 0., -1., 100., 100., 100., 100., -100., 100., 100., 100., 120., 100.,
+            100., 105., -100., 100., 100., 105.,
+        ];
+        index.add(some_data).unwrap();
+        assert_eq!(index.ntotal(), 5);
+
+        // keep only even IDs
+        let sel = IdSelector::batch(&[Idx::new(0), Idx::new(2), Idx::new(4)]).unwrap();
+        let params = SearchParameters::with_selector(&sel).unwrap();
+        let my_query = [100.; D as usize];
+        // flat index can be used behind an immutable ref
+        let result = ConcurrentIndex::search_with_params(&index, &my_query, 3, &params).unwrap();
+        assert_eq!(
+            result.labels,
+            vec![4, 0, 2].into_iter().map(Idx::new).collect::<Vec<_>>()
+        );
+        assert!(result.distances.iter().all(|x| *x > 0.));
+    }
+
     #[test]
     fn flat_index_assign() {
         let mut index = FlatIndexImpl::new(D, MetricType::L2).unwrap();
@@ -367,4 +393,28 @@ mod tests {
         };
         assert_eq!(index.ntotal(), 5);
     }
+
+    #[test]
+    fn flat_index_compute_residual() {
+        let mut index = FlatIndexImpl::new_l2(4).unwrap();
+        index.add(&[1., 1., 1., 1., 2., 0., 2., 0.]).unwrap();
+
+        let residual = index
+            .compute_residual(&[1., 2., 3., 4.], Idx::new(1))
+            .unwrap();
+        assert_eq!(residual, vec![-1., 2., 1., 4.]);
+
+        let residuals = index
+            .compute_residual_n(
+                &[1., 2., 3., 4., 1., 2., 3., 4.],
+                &[Idx::new(0), Idx::new(1)],
+            )
+            .unwrap();
+        assert_eq!(residuals, vec![0., 1., 2., 3., -1., 2., 1., 4.]);
+
+        assert!(index.compute_residual(&[1., 2., 3.], Idx::new(0)).is_err());
+        assert!(index
+            .compute_residual_n(&[1., 2., 3., 4.], &[Idx::new(0), Idx::new(1)])
+            .is_err());
+    }
 }
@@ -297,7 +297,10 @@ impl FlatIndexImpl {
     }
 }
 
-impl IVFFlatIndexImpl {
+impl<Q> IVFFlatIndexImpl<Q>
+where
+    Q: NativeIndex + CpuIndex,
+{
     /// Build a GPU in from the given CPU native index, yielding two
     /// independent indices. The operation fails if the index does
     /// not provide GPU support.
@@ -305,7 +308,7 @@ impl IVFFlatIndexImpl {
         &self,
         gpu_res: &'gpu G,
         device: i32,
-    ) -> Result<GpuIndexImpl<'gpu, IVFFlatIndexImpl>>
+    ) -> Result<GpuIndexImpl<'gpu, IVFFlatIndexImpl<Q>>>
     where
         G: GpuResourcesProvider,
     {
@@ -319,7 +322,7 @@ impl IVFFlatIndexImpl {
         self,
         gpu_res: &'gpu G,
         device: i32,
-    ) -> Result<GpuIndexImpl<'gpu, IVFFlatIndexImpl>>
+    ) -> Result<GpuIndexImpl<'gpu, IVFFlatIndexImpl<Q>>>
     where
         G: GpuResourcesProvider,
     {
@@ -336,7 +339,7 @@ impl IVFFlatIndexImpl {
         &self,
         gpu_res: &'gpu [G],
         devices: &[i32],
-    ) -> Result<GpuIndexImpl<'gpu, IVFFlatIndexImpl>>
+    ) -> Result<GpuIndexImpl<'gpu, IVFFlatIndexImpl<Q>>>
     where
         G: GpuResourcesProvider,
     {
@@ -354,7 +357,7 @@ impl IVFFlatIndexImpl {
         self,
         gpu_res: &'gpu [G],
         devices: &[i32],
-    ) -> Result<GpuIndexImpl<'gpu, IVFFlatIndexImpl>>
+    ) -> Result<GpuIndexImpl<'gpu, IVFFlatIndexImpl<Q>>>
     where
         G: GpuResourcesProvider,
     {
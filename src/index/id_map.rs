@@ -14,6 +14,12 @@
 //! [Faiss wiki]: https://github.com/facebookresearch/faiss/wiki/Pre--and-post-processing#faiss-id-mapping
 //! [`IdMap`]: struct.IdMap.html
 //!
+//! The [`IdMap2`] wrapper additionally keeps a reverse mapping from the
+//! arbitrary IDs to the internal ones, so that vectors can be reconstructed
+//! by their arbitrary ID, and removed more efficiently.
+//!
+//! [`IdMap2`]: struct.IdMap2.html
+//!
 //! # Examples
 //!
 //! A flat index does not support arbitrary ID mapping, but `IdMap` solves this:
@@ -57,7 +63,7 @@ use crate::error::{Error, Result};
 use crate::faiss_try;
 use crate::index::{
     self, AssignSearchResult, ConcurrentIndex, CpuIndex, FromInnerPtr, Idx, Index, NativeIndex,
-    RangeSearchResult, SearchResult,
+    RangeSearchResult, SearchResult, TryClone,
 };
 use crate::selector::IdSelector;
 use faiss_sys::*;
@@ -138,6 +144,8 @@ where
     /// already owned by this ID map. Therefore, it is undefined behavior to
     /// create a high-level index value from this pointer without first
     /// decoupling this ownership. See [`into_inner`] for a safe alternative.
+    ///
+    /// [`into_inner`]: #method.into_inner
     pub fn index_inner_ptr(&self) -> *mut FaissIndex {
         self.index_inner
     }
@@ -219,10 +227,196 @@ impl IndexImpl {
     }
 }
 
+/// Wrapper for implementing arbitrary ID mapping to an index,
+/// with a reverse map for reconstructing vectors by their arbitrary ID.
+///
+/// See the [module level documentation] for more information.
+///
+/// [module level documentation]: ./index.html
+#[derive(Debug)]
+pub struct IdMap2<I> {
+    inner: *mut FaissIndexIDMap2,
+    index_inner: *mut FaissIndex,
+    phantom: PhantomData<I>,
+}
+
+unsafe impl<I: Send> Send for IdMap2<I> {}
+unsafe impl<I: Sync> Sync for IdMap2<I> {}
+impl<I: CpuIndex> CpuIndex for IdMap2<I> {}
+
+impl<I> NativeIndex for IdMap2<I> {
+    fn inner_ptr(&self) -> *mut FaissIndex {
+        self.inner
+    }
+}
+
+impl<I> Drop for IdMap2<I> {
+    fn drop(&mut self) {
+        unsafe {
+            faiss_Index_free(self.inner);
+        }
+    }
+}
+
+impl<I> IdMap2<I>
+where
+    I: NativeIndex,
+{
+    /// Augment an index with arbitrary ID mapping and a reverse map.
+    pub fn new(index: I) -> Result<Self> {
+        unsafe {
+            let index_inner = index.inner_ptr();
+            let mut inner_ptr = ptr::null_mut();
+            faiss_try(faiss_IndexIDMap2_new(&mut inner_ptr, index_inner))?;
+            // let IDMap2 take ownership of the index
+            faiss_IndexIDMap2_set_own_fields(inner_ptr, 1);
+            mem::forget(index);
+
+            Ok(IdMap2 {
+                inner: inner_ptr,
+                index_inner,
+                phantom: PhantomData,
+            })
+        }
+    }
+
+    /// Retrieve a slice of the internal ID map.
+    pub fn id_map(&self) -> &[Idx] {
+        unsafe {
+            let mut id_ptr = ptr::null_mut();
+            let mut psize = 0;
+            faiss_IndexIDMap2_id_map(self.inner, &mut id_ptr, &mut psize);
+            ::std::slice::from_raw_parts(id_ptr as *const _, psize)
+        }
+    }
+
+    /// Rebuild the reverse map from scratch.
+    ///
+    /// This is only needed after the ID map is modified
+    /// through means other than this wrapper.
+    pub fn construct_rev_map(&mut self) -> Result<()> {
+        unsafe {
+            faiss_try(faiss_IndexIDMap2_construct_rev_map(self.inner))?;
+            Ok(())
+        }
+    }
+
+    /// Obtain the raw pointer to the internal index.
+    ///
+    /// # Safety
+    ///
+    /// While this method is safe, note that the returned index pointer is
+    /// already owned by this ID map. Therefore, it is undefined behavior to
+    /// create a high-level index value from this pointer without first
+    /// decoupling this ownership. See [`into_inner`] for a safe alternative.
+    ///
+    /// [`into_inner`]: #method.into_inner
+    pub fn index_inner_ptr(&self) -> *mut FaissIndex {
+        self.index_inner
+    }
+
+    /// Discard the ID map, recovering the index originally created without it.
+    pub fn into_inner(self) -> I
+    where
+        I: FromInnerPtr,
+    {
+        unsafe {
+            // make id map disown the index
+            faiss_IndexIDMap2_set_own_fields(self.inner, 0);
+            // now it's safe to build a managed index
+            // (`index_inner` is expected to always point to a valid index)
+            I::from_inner_ptr(self.index_inner)
+        }
+    }
+
+    /// Discard the ID map, recovering the index originally created without it.
+    /// Safety build managed index from pointer.
+    pub fn try_into_inner(self) -> Result<I>
+    where
+        I: index::TryFromInnerPtr,
+    {
+        unsafe {
+            // make id map disown the index
+            faiss_IndexIDMap2_set_own_fields(self.inner, 0);
+            // now it's safe to build a managed index
+            // (`index_inner` is expected to always point to a valid index)
+            I::try_from_inner_ptr(self.index_inner)
+        }
+    }
+
+    /// Specialization of the index type inside `IdMap2`.
+    pub fn try_cast_inner_index<B>(self) -> Result<IdMap2<B>>
+    where
+        B: index::TryFromInnerPtr,
+    {
+        // safety: index_inner is expected to always point to a valid index
+        let r = unsafe { B::try_from_inner_ptr(self.index_inner) };
+        if let Ok(index) = r {
+            let res = IdMap2 {
+                inner: self.inner,
+                index_inner: index.inner_ptr(),
+                phantom: PhantomData,
+            };
+            mem::forget(index);
+            mem::forget(self);
+
+            Ok(res)
+        } else {
+            Err(Error::BadCast)
+        }
+    }
+}
+
+impl_index!(IdMap2<I>, I);
+impl_concurrent_index!(IdMap2<I>, I: ConcurrentIndex);
+
+impl<I> TryClone for IdMap2<I> {
+    fn try_clone(&self) -> Result<Self>
+    where
+        Self: Sized,
+    {
+        unsafe {
+            let mut new_index_ptr = ptr::null_mut();
+            faiss_try(faiss_clone_index(self.inner, &mut new_index_ptr))?;
+            // the cloned ID map owns a clone of the internal index
+            let index_inner = faiss_IndexIDMap2_sub_index(new_index_ptr);
+            Ok(IdMap2 {
+                inner: new_index_ptr,
+                index_inner,
+                phantom: PhantomData,
+            })
+        }
+    }
+}
+
+impl IndexImpl {
+    /// Attempt a dynamic cast of the index to one that is [ID-mapped][1]
+    /// with a reverse map.
+    ///
+    /// [1]: crate::IdMap2
+    pub fn into_id_map2(self) -> Result<IdMap2<IndexImpl>> {
+        unsafe {
+            let new_inner = faiss_IndexIDMap2_cast(self.inner_ptr());
+            if new_inner.is_null() {
+                Err(Error::BadCast)
+            } else {
+                mem::forget(self);
+                let index_inner = faiss_IndexIDMap2_sub_index(new_inner);
+                Ok(IdMap2 {
+                    inner: new_inner,
+                    index_inner,
+                    phantom: PhantomData,
+                })
+            }
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
-    use super::IdMap;
-    use crate::index::{flat::FlatIndexImpl, index_factory, Idx, Index, IndexImpl};
+    use super::{IdMap, IdMap2};
+    use crate::index::io::{read_index, write_index};
+    use crate::index::{flat::FlatIndexImpl, index_factory, Idx, Index, IndexImpl, TryClone};
     use crate::selector::IdSelector;
     use crate::MetricType;
 
@@ -334,4 +528,84 @@ mod tests {
 
         assert_eq!(id_map.d(), 4);
     }
+
+    #[test]
+    fn id_map2_reconstruct_by_id() {
+        let index = FlatIndexImpl::new_l2(4).unwrap();
+        let mut index = IdMap2::new(index).unwrap();
+        let some_data = &[2.3_f32, 0.0, -1., 1., 1., 1., 1., 4.5, 2.3, 7.6, 1., 2.2];
+        index
+            .add_with_ids(some_data, &[Idx::new(4), Idx::new(8), Idx::new(12)])
+            .unwrap();
+        assert_eq!(index.ntotal(), 3);
+        assert_eq!(index.id_map(), &[Idx::new(4), Idx::new(8), Idx::new(12)]);
+
+        let mut out = [0.; 4];
+        index.reconstruct(Idx::new(8), &mut out).unwrap();
+        assert_eq!(out, [1., 1., 1., 4.5]);
+        // not an external ID
+        assert!(index.reconstruct(Idx::new(1), &mut out).is_err());
+
+        let id_sel = IdSelector::batch(&[Idx::new(4)]).unwrap();
+        assert_eq!(index.remove_ids(&id_sel).unwrap(), 1);
+        assert_eq!(index.ntotal(), 2);
+        index.reconstruct(Idx::new(12), &mut out).unwrap();
+        assert_eq!(out, [2.3, 7.6, 1., 2.2]);
+    }
+
+    #[test]
+    fn id_map2_clone() {
+        let index = FlatIndexImpl::new_l2(4).unwrap();
+        let mut index = IdMap2::new(index).unwrap();
+        index
+            .add_with_ids(&[1., 2., 3., 4.], &[Idx::new(42)])
+            .unwrap();
+
+        let mut cloned = index.try_clone().unwrap();
+        cloned
+            .add_with_ids(&[5., 6., 7., 8.], &[Idx::new(43)])
+            .unwrap();
+        assert_eq!(index.ntotal(), 1);
+        assert_eq!(cloned.ntotal(), 2);
+
+        let mut out = [0.; 4];
+        cloned.reconstruct(Idx::new(42), &mut out).unwrap();
+        assert_eq!(out, [1., 2., 3., 4.]);
+        let inner: FlatIndexImpl = cloned.try_into_inner().unwrap();
+        assert_eq!(inner.ntotal(), 2);
+    }
+
+    #[test]
+    fn id_map2_write_read() {
+        let index = FlatIndexImpl::new_l2(4).unwrap();
+        let mut index = IdMap2::new(index).unwrap();
+        index
+            .add_with_ids(
+                &[1., 2., 3., 4., 5., 6., 7., 8.],
+                &[Idx::new(7), Idx::new(3)],
+            )
+            .unwrap();
+
+        let filepath = ::std::env::temp_dir().join("test_id_map2_write_read.index");
+        let filename = filepath.to_str().unwrap();
+        write_index(&index, filename).unwrap();
+        let index = read_index(filename).unwrap();
+        ::std::fs::remove_file(&filepath).unwrap();
+
+        let index = index.into_id_map2().unwrap();
+        assert_eq!(index.ntotal(), 2);
+        let mut out = [0.; 4];
+        index.reconstruct(Idx::new(3), &mut out).unwrap();
+        assert_eq!(out, [5., 6., 7., 8.]);
+    }
+
+    #[test]
+    fn index_impl_to_id_map2() {
+        let index = index_factory(4, "IDMap2,Flat", MetricType::L2).unwrap();
+        let id_map = index.into_id_map2().unwrap();
+        assert_eq!(id_map.d(), 4);
+
+        let index = index_factory(4, "IDMap,Flat", MetricType::L2).unwrap();
+        assert!(index.into_id_map2().is_err());
+    }
 }
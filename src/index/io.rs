@@ -1,17 +1,33 @@
 //! Index I/O functions
+//!
+//! Indexes can be written to and read from files (by name or by path), byte
+//! buffers, or any
+//! [`Read`] or [`Write`] implementation. Streaming to Rust readers and writers
+//! is supported on Linux, macOS and the BSDs. On other platforms, these
+//! functions fail with an I/O error.
+//!
+//! [`Read`]: https://doc.rust-lang.org/std/io/trait.Read.html
+//! [`Write`]: https://doc.rust-lang.org/std/io/trait.Write.html
 
 use crate::error::{Error, Result};
 use crate::faiss_try;
+use crate::index::binary::{BinaryIndexImpl, NativeBinaryIndex};
+use crate::index::stream;
 use crate::index::{CpuIndex, FromInnerPtr, IndexImpl, NativeIndex};
 use faiss_sys::*;
 use std::ffi::CString;
-use std::os::raw::c_int;
+use std::io::{Read, Write};
+use std::path::Path;
 use std::ptr;
 
 pub use super::io_flags::IoFlags;
 
 /// Write an index to a file.
 ///
+/// See [`write_index_to_path`] for a variant which accepts any path.
+///
+/// [`write_index_to_path`]: fn.write_index_to_path.html
+///
 /// # Error
 ///
 /// This function returns an error if the description contains any byte with the value `\0` (since
@@ -22,17 +38,15 @@ where
     I: CpuIndex,
     P: AsRef<str>,
 {
-    unsafe {
-        let f = file_name.as_ref();
-        let f = CString::new(f).map_err(|_| Error::BadFilePath)?;
-
-        faiss_try(faiss_write_index_fname(index.inner_ptr(), f.as_ptr()))?;
-        Ok(())
-    }
+    write_index_to_path(index, file_name.as_ref())
 }
 
 /// Read an index from a file.
 ///
+/// See [`read_index_from_path`] for a variant which accepts any path.
+///
+/// [`read_index_from_path`]: fn.read_index_from_path.html
+///
 /// # Error
 ///
 /// This function returns an error if the description contains any byte with the value `\0` (since
@@ -40,50 +54,379 @@ where
 pub fn read_index<P>(file_name: P) -> Result<IndexImpl>
 where
     P: AsRef<str>,
+{
+    read_index_from_path(file_name.as_ref())
+}
+
+/// Read an index from a file with I/O flags.
+///
+/// You can memory map some index types with this.
+///
+/// # Error
+///
+/// This function returns an error if the description contains any byte with the value `\0` (since
+/// it cannot be converted to a C string), or if the internal index reading operation fails.
+pub fn read_index_with_flags<P>(file_name: P, io_flags: IoFlags) -> Result<IndexImpl>
+where
+    P: AsRef<str>,
+{
+    read_index_from_path_with_flags(file_name.as_ref(), io_flags)
+}
+
+/// Write a binary index to a file.
+///
+/// # Error
+///
+/// This function returns an error if the file name contains any byte with the value `\0` (since
+/// it cannot be converted to a C string), or if the internal index writing operation fails.
+pub fn write_index_binary<I, P>(index: &I, file_name: P) -> Result<()>
+where
+    I: NativeBinaryIndex,
+    P: AsRef<str>,
+{
+    write_index_binary_to_path(index, file_name.as_ref())
+}
+
+/// Read a binary index from a file.
+///
+/// # Error
+///
+/// This function returns an error if the file name contains any byte with the value `\0` (since
+/// it cannot be converted to a C string), or if the internal index reading operation fails.
+pub fn read_index_binary<P>(file_name: P) -> Result<BinaryIndexImpl>
+where
+    P: AsRef<str>,
+{
+    read_index_binary_with_flags(file_name, IoFlags::MEM_RESIDENT)
+}
+
+/// Read a binary index from a file with I/O flags.
+///
+/// # Error
+///
+/// This function returns an error if the file name contains any byte with the value `\0` (since
+/// it cannot be converted to a C string), or if the internal index reading operation fails.
+pub fn read_index_binary_with_flags<P>(file_name: P, io_flags: IoFlags) -> Result<BinaryIndexImpl>
+where
+    P: AsRef<str>,
+{
+    read_index_binary_from_path_with_flags(file_name.as_ref(), io_flags)
+}
+
+/// Write an index to the file at the given path.
+///
+/// Unlike [`write_index`], paths which are not valid UTF-8 are accepted on
+/// Unix platforms.
+///
+/// [`write_index`]: fn.write_index.html
+///
+/// # Error
+///
+/// This function returns an error if the path contains any byte with the value `\0` (since
+/// it cannot be converted to a C string), or if the internal index writing operation fails.
+pub fn write_index_to_path<I, P>(index: &I, path: P) -> Result<()>
+where
+    I: NativeIndex,
+    I: CpuIndex,
+    P: AsRef<Path>,
+{
+    unsafe {
+        let f = path_to_cstring(path.as_ref())?;
+        faiss_try(faiss_write_index_fname(index.inner_ptr(), f.as_ptr()))?;
+        Ok(())
+    }
+}
+
+/// Read an index from the file at the given path.
+///
+/// Unlike [`read_index`], paths which are not valid UTF-8 are accepted on
+/// Unix platforms.
+///
+/// [`read_index`]: fn.read_index.html
+///
+/// # Error
+///
+/// This function returns an error if the path contains any byte with the value `\0` (since
+/// it cannot be converted to a C string), or if the internal index reading operation fails.
+pub fn read_index_from_path<P>(path: P) -> Result<IndexImpl>
+where
+    P: AsRef<Path>,
+{
+    read_index_from_path_with_flags(path, IoFlags::MEM_RESIDENT)
+}
+
+/// Read an index from the file at the given path with I/O flags.
+///
+/// The flags make it possible to open large indexes partially or lazily,
+/// such as by memory mapping them or skipping the IVF data.
+///
+/// # Error
+///
+/// This function returns an error if the path contains any byte with the value `\0` (since
+/// it cannot be converted to a C string), or if the internal index reading operation fails.
+pub fn read_index_from_path_with_flags<P>(path: P, io_flags: IoFlags) -> Result<IndexImpl>
+where
+    P: AsRef<Path>,
 {
     unsafe {
-        let f = file_name.as_ref();
-        let f = CString::new(f).map_err(|_| Error::BadFilePath)?;
+        let f = path_to_cstring(path.as_ref())?;
         let mut inner = ptr::null_mut();
         faiss_try(faiss_read_index_fname(
             f.as_ptr(),
-            IoFlags::MEM_RESIDENT.into(),
+            io_flags.into(),
             &mut inner,
         ))?;
         Ok(IndexImpl::from_inner_ptr(inner))
     }
 }
 
-/// Read an index from a file with I/O flags.
+/// Write a binary index to the file at the given path.
 ///
-/// You can memory map some index types with this.
+/// # Error
+///
+/// This function returns an error if the path contains any byte with the value `\0` (since
+/// it cannot be converted to a C string), or if the internal index writing operation fails.
+pub fn write_index_binary_to_path<I, P>(index: &I, path: P) -> Result<()>
+where
+    I: NativeBinaryIndex,
+    P: AsRef<Path>,
+{
+    unsafe {
+        let f = path_to_cstring(path.as_ref())?;
+        faiss_try(faiss_write_index_binary_fname(
+            index.inner_ptr(),
+            f.as_ptr(),
+        ))?;
+        Ok(())
+    }
+}
+
+/// Read a binary index from the file at the given path.
 ///
 /// # Error
 ///
-/// This function returns an error if the description contains any byte with the value `\0` (since
+/// This function returns an error if the path contains any byte with the value `\0` (since
 /// it cannot be converted to a C string), or if the internal index reading operation fails.
-pub fn read_index_with_flags<P>(file_name: P, io_flags: IoFlags) -> Result<IndexImpl>
+pub fn read_index_binary_from_path<P>(path: P) -> Result<BinaryIndexImpl>
 where
-    P: AsRef<str>,
+    P: AsRef<Path>,
+{
+    read_index_binary_from_path_with_flags(path, IoFlags::MEM_RESIDENT)
+}
+
+/// Read a binary index from the file at the given path with I/O flags.
+///
+/// # Error
+///
+/// This function returns an error if the path contains any byte with the value `\0` (since
+/// it cannot be converted to a C string), or if the internal index reading operation fails.
+pub fn read_index_binary_from_path_with_flags<P>(
+    path: P,
+    io_flags: IoFlags,
+) -> Result<BinaryIndexImpl>
+where
+    P: AsRef<Path>,
 {
     unsafe {
-        let f = file_name.as_ref();
-        let f = CString::new(f).map_err(|_| Error::BadFilePath)?;
+        let f = path_to_cstring(path.as_ref())?;
         let mut inner = ptr::null_mut();
-        faiss_try(faiss_read_index_fname(
+        faiss_try(faiss_read_index_binary_fname(
             f.as_ptr(),
-            io_flags.0 as c_int,
+            io_flags.into(),
             &mut inner,
         ))?;
-        Ok(IndexImpl::from_inner_ptr(inner))
+        Ok(BinaryIndexImpl::from_inner_ptr(inner))
     }
 }
 
+#[cfg(unix)]
+pub(crate) fn path_to_cstring(path: &Path) -> Result<CString> {
+    use std::os::unix::ffi::OsStrExt;
+    CString::new(path.as_os_str().as_bytes()).map_err(|_| Error::BadFilePath)
+}
+
+#[cfg(not(unix))]
+pub(crate) fn path_to_cstring(path: &Path) -> Result<CString> {
+    let path = path.to_str().ok_or(Error::BadFilePath)?;
+    CString::new(path).map_err(|_| Error::BadFilePath)
+}
+
+/// Write an index to the given writer,
+/// in the same format as [`write_index`].
+///
+/// The writer is flushed once the index is written.
+///
+/// [`write_index`]: fn.write_index.html
+///
+/// # Error
+///
+/// This function returns an [`Error::Io`] if the writer fails, or a native
+/// error if the internal index writing operation fails.
+///
+/// [`Error::Io`]: ../../error/enum.Error.html#variant.Io
+pub fn write_index_to<I, W>(index: &I, mut writer: W) -> Result<()>
+where
+    I: NativeIndex,
+    I: CpuIndex,
+    W: Write,
+{
+    stream::with_writer(&mut writer, |f| unsafe {
+        faiss_try(faiss_write_index(index.inner_ptr(), f))?;
+        Ok(())
+    })
+}
+
+/// Read an index from the given reader.
+///
+/// Only the bytes of the index are taken from the reader,
+/// so it can be followed by other data.
+/// The reader is read from without any buffering,
+/// so consider wrapping readers with costly reads (such as files or sockets)
+/// in a [`BufReader`].
+///
+/// # Error
+///
+/// This function returns an [`Error::Io`] if the reader fails, or a native
+/// error if the internal index reading operation fails
+/// (such as when the data ends prematurely).
+///
+/// [`Error::Io`]: ../../error/enum.Error.html#variant.Io
+/// [`BufReader`]: https://doc.rust-lang.org/std/io/struct.BufReader.html
+pub fn read_index_from<R>(mut reader: R) -> Result<IndexImpl>
+where
+    R: Read,
+{
+    stream::with_reader(&mut reader, |f| unsafe {
+        let mut inner = ptr::null_mut();
+        faiss_try(faiss_read_index(
+            f,
+            IoFlags::MEM_RESIDENT.into(),
+            &mut inner,
+        ))?;
+        Ok(IndexImpl::from_inner_ptr(inner))
+    })
+}
+
+/// Write a binary index to the given writer,
+/// in the same format as [`write_index_binary`].
+///
+/// The writer is flushed once the index is written.
+///
+/// [`write_index_binary`]: fn.write_index_binary.html
+///
+/// # Error
+///
+/// This function returns an [`Error::Io`] if the writer fails, or a native
+/// error if the internal index writing operation fails.
+///
+/// [`Error::Io`]: ../../error/enum.Error.html#variant.Io
+pub fn write_index_binary_to<I, W>(index: &I, mut writer: W) -> Result<()>
+where
+    I: NativeBinaryIndex,
+    W: Write,
+{
+    stream::with_writer(&mut writer, |f| unsafe {
+        faiss_try(faiss_write_index_binary(index.inner_ptr(), f))?;
+        Ok(())
+    })
+}
+
+/// Read a binary index from the given reader.
+///
+/// Only the bytes of the binary index are taken from the reader,
+/// so it can be followed by other data.
+/// The reader is read from without any buffering,
+/// so consider wrapping readers with costly reads (such as files or sockets)
+/// in a [`BufReader`].
+///
+/// # Error
+///
+/// This function returns an [`Error::Io`] if the reader fails, or a native
+/// error if the internal index reading operation fails
+/// (such as when the data ends prematurely).
+///
+/// [`Error::Io`]: ../../error/enum.Error.html#variant.Io
+/// [`BufReader`]: https://doc.rust-lang.org/std/io/struct.BufReader.html
+pub fn read_index_binary_from<R>(mut reader: R) -> Result<BinaryIndexImpl>
+where
+    R: Read,
+{
+    stream::with_reader(&mut reader, |f| unsafe {
+        let mut inner = ptr::null_mut();
+        faiss_try(faiss_read_index_binary(
+            f,
+            IoFlags::MEM_RESIDENT.into(),
+            &mut inner,
+        ))?;
+        Ok(BinaryIndexImpl::from_inner_ptr(inner))
+    })
+}
+
+/// Write an index into a new byte buffer,
+/// in the same format as [`write_index`].
+///
+/// [`write_index`]: fn.write_index.html
+///
+/// # Error
+///
+/// This function returns an error if the internal index writing operation fails.
+pub fn write_index_to_vec<I>(index: &I) -> Result<Vec<u8>>
+where
+    I: NativeIndex,
+    I: CpuIndex,
+{
+    let mut buf = Vec::new();
+    write_index_to(index, &mut buf)?;
+    Ok(buf)
+}
+
+/// Read an index from a byte buffer,
+/// such as one produced by [`write_index_to_vec`].
+///
+/// [`write_index_to_vec`]: fn.write_index_to_vec.html
+///
+/// # Error
+///
+/// This function returns an error if the internal index reading operation fails.
+pub fn read_index_from_slice(bytes: &[u8]) -> Result<IndexImpl> {
+    read_index_from(bytes)
+}
+
+/// Write a binary index into a new byte buffer,
+/// in the same format as [`write_index_binary`].
+///
+/// [`write_index_binary`]: fn.write_index_binary.html
+///
+/// # Error
+///
+/// This function returns an error if the internal index writing operation fails.
+pub fn write_index_binary_to_vec<I>(index: &I) -> Result<Vec<u8>>
+where
+    I: NativeBinaryIndex,
+{
+    let mut buf = Vec::new();
+    write_index_binary_to(index, &mut buf)?;
+    Ok(buf)
+}
+
+/// Read a binary index from a byte buffer,
+/// such as one produced by [`write_index_binary_to_vec`].
+///
+/// [`write_index_binary_to_vec`]: fn.write_index_binary_to_vec.html
+///
+/// # Error
+///
+/// This function returns an error if the internal index reading operation fails.
+pub fn read_index_binary_from_slice(bytes: &[u8]) -> Result<BinaryIndexImpl> {
+    read_index_binary_from(bytes)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::index::binary::{binary_index_factory, BinaryIndex};
     use crate::index::flat::FlatIndex;
-    use crate::index::Index;
+    use crate::index::{Idx, Index};
     const D: u32 = 8;
 
     #[test]
@@ -113,4 +456,148 @@ mod tests {
         // we just want to ensure the method signature is right here
         assert!(index.is_err());
     }
+
+    #[test]
+    fn write_read_path() {
+        let mut index = crate::index::index_factory(D, "IVF1,Flat", crate::MetricType::L2).unwrap();
+        let some_data = &[1.; 5 * D as usize];
+        index.train(some_data).unwrap();
+        index.add(some_data).unwrap();
+
+        let filepath = ::std::env::temp_dir().join("test_write_read_path.index");
+        write_index_to_path(&index, &filepath).unwrap();
+        let index = read_index_from_path(&filepath).unwrap();
+        assert_eq!(index.ntotal(), 5);
+        let index = read_index_from_path_with_flags(
+            filepath.as_path(),
+            IoFlags::READ_ONLY | IoFlags::SKIP_IVF_DATA,
+        )
+        .unwrap();
+        assert_eq!(index.d(), D);
+        ::std::fs::remove_file(&filepath).unwrap();
+    }
+
+    #[test]
+    fn write_read_binary() {
+        let mut index = binary_index_factory(32, "BFlat").unwrap();
+        let some_data = &[
+            0x00_u8, 0x00, 0x00, 0x00, 0xFF, 0xFF, 0xFF, 0xFF, 0x0F, 0x00, 0x00, 0x00,
+        ];
+        index.add(some_data).unwrap();
+        assert_eq!(index.ntotal(), 3);
+
+        let filepath = ::std::env::temp_dir().join("test_write_read_binary.index");
+        let filename = filepath.to_str().unwrap();
+        write_index_binary(&index, filename).unwrap();
+        let index = read_index_binary(filename).unwrap();
+        assert_eq!(index.ntotal(), 3);
+        assert_eq!(index.d(), 32);
+        ::std::fs::remove_file(&filepath).unwrap();
+    }
+
+    #[test]
+    fn write_read_in_memory() {
+        let mut index = FlatIndex::new_l2(D).unwrap();
+        let some_data = &[
+            7.5_f32, -7.5, 7.5, -7.5, 7.5, 7.5, 7.5, 7.5, -1., 1., 1., 1., 1., 1., 1., -1., 4.,
+            -4., -8., 1., 1., 2., 4., -1., 8., 8., 10., -10., -10., 10., -10., 10., 16., 16., 32.,
+            25., 20., 20., 40., 15.,
+        ];
+        index.add(some_data).unwrap();
+
+        let bytes = write_index_to_vec(&index).unwrap();
+        assert!(!bytes.is_empty());
+        let index = read_index_from_slice(&bytes).unwrap();
+        assert_eq!(index.ntotal(), 5);
+        assert_eq!(index.d(), D);
+
+        let mut out = [0.; D as usize];
+        index.reconstruct(Idx::new(1), &mut out).unwrap();
+        assert_eq!(&out[..], &some_data[8..16]);
+
+        assert!(read_index_from_slice(&bytes[..bytes.len() / 2]).is_err());
+        assert!(read_index_from_slice(&[]).is_err());
+    }
+
+    #[test]
+    fn write_read_binary_in_memory() {
+        let mut index = binary_index_factory(32, "BFlat").unwrap();
+        let some_data = &[
+            0x00_u8, 0x00, 0x00, 0x00, 0xFF, 0xFF, 0xFF, 0xFF, 0x0F, 0x00, 0x00, 0x00,
+        ];
+        index.add(some_data).unwrap();
+
+        let bytes = write_index_binary_to_vec(&index).unwrap();
+        let index = read_index_binary_from_slice(&bytes).unwrap();
+        assert_eq!(index.ntotal(), 3);
+        assert_eq!(index.d(), 32);
+    }
+
+    #[test]
+    fn write_read_stream() {
+        let mut index = FlatIndex::new_l2(D).unwrap();
+        index.add(&[1.; 3 * D as usize]).unwrap();
+
+        let mut buf = std::io::Cursor::new(Vec::new());
+        write_index_to(&index, &mut buf).unwrap();
+        buf.set_position(0);
+        let index = read_index_from(&mut buf).unwrap();
+        assert_eq!(index.ntotal(), 3);
+        assert_eq!(index.d(), D);
+    }
+
+    #[test]
+    fn read_stream_with_trailing_data() {
+        use std::io::{Cursor, Read};
+
+        let mut index = FlatIndex::new_l2(D).unwrap();
+        index.add(&[1.; 3 * D as usize]).unwrap();
+
+        let mut bytes = write_index_to_vec(&index).unwrap();
+        let len = bytes.len() as u64;
+        bytes.extend_from_slice(b"trailing data");
+        let mut cursor = Cursor::new(bytes);
+        let index = read_index_from(&mut cursor).unwrap();
+        assert_eq!(index.ntotal(), 3);
+        assert_eq!(cursor.position(), len);
+
+        let mut rest = String::new();
+        cursor.read_to_string(&mut rest).unwrap();
+        assert_eq!(rest, "trailing data");
+    }
+
+    #[test]
+    fn write_read_stream_errors() {
+        use crate::error::Error;
+        use std::io;
+
+        struct Failing;
+
+        impl io::Write for Failing {
+            fn write(&mut self, _buf: &[u8]) -> io::Result<usize> {
+                Err(io::Error::new(io::ErrorKind::BrokenPipe, "broken"))
+            }
+
+            fn flush(&mut self) -> io::Result<()> {
+                Ok(())
+            }
+        }
+
+        impl io::Read for Failing {
+            fn read(&mut self, _buf: &mut [u8]) -> io::Result<usize> {
+                Err(io::Error::new(io::ErrorKind::ConnectionReset, "reset"))
+            }
+        }
+
+        let mut index = FlatIndex::new_l2(D).unwrap();
+        index.add(&[1.; 3 * D as usize]).unwrap();
+        match write_index_to(&index, Failing) {
+            Err(Error::Io(e)) => assert_eq!(e.kind(), io::ErrorKind::BrokenPipe),
+            r => panic!("unexpected result: {:?}", r),
+        }
+        match read_index_from(Failing) {
+            Err(Error::Io(e)) => assert_eq!(e.kind(), io::ErrorKind::ConnectionReset),
+            r => panic!("unexpected result: {:?}", r.map(|_| ())),
+        }
+    }
 }
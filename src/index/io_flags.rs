@@ -1,18 +1,44 @@
 //! Module containing the I/O flags.
 
+use std::fmt;
+
 /// I/O flags used during index reading.
 ///
 /// Note that not all flags are applicable to all index types.
-#[derive(Debug, Copy, Clone, Eq, Hash, PartialEq)]
-pub struct IoFlags(pub(crate) u16);
+#[derive(Copy, Clone, Eq, Hash, PartialEq)]
+pub struct IoFlags(pub(crate) i32);
 
 impl IoFlags {
     /// Load entire index into memory (default behavior)
     pub const MEM_RESIDENT: Self = IoFlags(0x00);
     /// Memory-map index
+    ///
+    /// This is the value defined by the Faiss C API,
+    /// which is the same as [`SKIP_STORAGE`](#associatedconstant.SKIP_STORAGE).
     pub const MEM_MAP: Self = IoFlags(0x01);
+    /// Do not read the storage of graph-based indexes (such as HNSW)
+    pub const SKIP_STORAGE: Self = IoFlags(0x01);
     /// Index is read-only
     pub const READ_ONLY: Self = IoFlags(0x02);
+    /// Strip the directory component from the file names of on-disk
+    /// inverted lists, and assume them to be in the same directory
+    /// as the index file
+    pub const ONDISK_SAME_DIR: Self = IoFlags(0x04);
+    /// Do not load the IVF data to memory, only the list sizes
+    pub const SKIP_IVF_DATA: Self = IoFlags(0x08);
+    /// Do not initialize the precomputed table after loading
+    pub const SKIP_PRECOMPUTE_TABLE: Self = IoFlags(0x10);
+    /// Do not compute the symmetric distance table of PQ-based indexes,
+    /// which prevents distances between indexed vectors from being computed
+    pub const PQ_SKIP_SDC_TABLE: Self = IoFlags(0x20);
+    /// Try to memory-map the inverted lists of IVF indexes
+    /// (loading them as on-disk inverted lists)
+    pub const MEM_MAP_IVF: Self = IoFlags(0x646f_0000 | IoFlags::SKIP_IVF_DATA.0);
+
+    /// Whether all of the flags in `other` are also set in these flags.
+    pub fn contains(self, other: IoFlags) -> bool {
+        self.0 & other.0 == other.0
+    }
 }
 
 impl Default for IoFlags {
@@ -21,6 +47,47 @@ impl Default for IoFlags {
     }
 }
 
+impl fmt::Debug for IoFlags {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        // composite flags come first, so that their bits are not reported twice.
+        // `MEM_MAP` and `SKIP_STORAGE` share the same bit, whose meaning
+        // depends on the index type, so it is reported as a raw value
+        const NAMES: [(IoFlags, &str); 6] = [
+            (IoFlags::MEM_MAP_IVF, "MEM_MAP_IVF"),
+            (IoFlags::READ_ONLY, "READ_ONLY"),
+            (IoFlags::ONDISK_SAME_DIR, "ONDISK_SAME_DIR"),
+            (IoFlags::SKIP_IVF_DATA, "SKIP_IVF_DATA"),
+            (IoFlags::SKIP_PRECOMPUTE_TABLE, "SKIP_PRECOMPUTE_TABLE"),
+            (IoFlags::PQ_SKIP_SDC_TABLE, "PQ_SKIP_SDC_TABLE"),
+        ];
+
+        f.write_str("IoFlags(")?;
+        if self.0 == 0 {
+            f.write_str("MEM_RESIDENT")?;
+        } else {
+            let mut rest = self.0;
+            let mut first = true;
+            for (flag, name) in NAMES.iter() {
+                if rest & flag.0 == flag.0 {
+                    if !first {
+                        f.write_str(" | ")?;
+                    }
+                    f.write_str(name)?;
+                    rest &= !flag.0;
+                    first = false;
+                }
+            }
+            if rest != 0 {
+                if !first {
+                    f.write_str(" | ")?;
+                }
+                write!(f, "{:#x}", rest)?;
+            }
+        }
+        f.write_str(")")
+    }
+}
+
 impl std::ops::BitOr for IoFlags {
     type Output = Self;
 
@@ -29,15 +96,23 @@ impl std::ops::BitOr for IoFlags {
     }
 }
 
+impl std::ops::BitAnd for IoFlags {
+    type Output = Self;
+
+    fn bitand(self, rhs: Self) -> Self::Output {
+        Self(self.0 & rhs.0)
+    }
+}
+
 impl From<i32> for IoFlags {
     fn from(n: i32) -> IoFlags {
-        IoFlags(n as u16)
+        IoFlags(n)
     }
 }
 
 impl From<IoFlags> for i32 {
     fn from(io_flag: IoFlags) -> i32 {
-        io_flag.0 as i32
+        io_flag.0
     }
 }
 
@@ -52,9 +127,43 @@ mod tests {
         assert_eq!(IoFlags(0x03), mmap | ro);
     }
 
+    #[test]
+    fn can_do_bitand_and_contains() {
+        let flags = IoFlags::READ_ONLY | IoFlags::SKIP_IVF_DATA;
+        assert_eq!(flags & IoFlags::READ_ONLY, IoFlags::READ_ONLY);
+        assert_eq!(flags & IoFlags::MEM_MAP, IoFlags::MEM_RESIDENT);
+        assert!(flags.contains(IoFlags::SKIP_IVF_DATA));
+        assert!(flags.contains(IoFlags::MEM_RESIDENT));
+        assert!(!flags.contains(IoFlags::ONDISK_SAME_DIR));
+        assert!(IoFlags::MEM_MAP_IVF.contains(IoFlags::SKIP_IVF_DATA));
+        assert!(!IoFlags::SKIP_IVF_DATA.contains(IoFlags::MEM_MAP_IVF));
+    }
+
     #[test]
     fn can_coerce_to_i32() {
         let mmap = IoFlags::MEM_MAP;
         assert_eq!(1, mmap.into());
+        assert_eq!(0x646f_0008, i32::from(IoFlags::MEM_MAP_IVF));
+    }
+
+    #[test]
+    fn debug_names() {
+        assert_eq!(format!("{:?}", IoFlags::default()), "IoFlags(MEM_RESIDENT)");
+        assert_eq!(
+            format!("{:?}", IoFlags::READ_ONLY | IoFlags::SKIP_PRECOMPUTE_TABLE),
+            "IoFlags(READ_ONLY | SKIP_PRECOMPUTE_TABLE)"
+        );
+        assert_eq!(
+            format!("{:?}", IoFlags::MEM_MAP_IVF | IoFlags::READ_ONLY),
+            "IoFlags(MEM_MAP_IVF | READ_ONLY)"
+        );
+        assert_eq!(
+            format!("{:?}", IoFlags(0x102)),
+            "IoFlags(READ_ONLY | 0x100)"
+        );
+        assert_eq!(
+            format!("{:?}", IoFlags::SKIP_STORAGE | IoFlags::READ_ONLY),
+            "IoFlags(READ_ONLY | 0x1)"
+        );
     }
 }
@@ -0,0 +1,478 @@
+//! Interface and implementation to IVF indexes of any kind.
+//!
+//! The [`IvfIndex`] trait gathers the operations shared by all indexes based
+//! on inverted lists, such as merging indexes and inspecting the lists.
+//! [`IVFIndexImpl`] is a native IVF index of unknown encoding, as obtained
+//! with [`IndexImpl::into_ivf`].
+//!
+//! [`IvfIndex`]: trait.IvfIndex.html
+//! [`IVFIndexImpl`]: struct.IVFIndexImpl.html
+//! [`IndexImpl::into_ivf`]: ../struct.IndexImpl.html#method.into_ivf
+//!
+//! # Examples
+//!
+//! ```
+//! use faiss::{index_factory, Index, MetricType};
+//! use faiss::index::ivf::IvfIndex;
+//! use faiss::index::TryClone;
+//! # fn run() -> Result<(), Box<dyn std::error::Error>> {
+//! let data = [0., 0., 0., 1., 10., 10., 10., 11.];
+//! let mut index = index_factory(2, "IVF2,Flat", MetricType::L2)?.into_ivf()?;
+//! index.train(&data)?;
+//! index.add(&data)?;
+//!
+//! // build another index with the same trained quantizer
+//! let mut nightly = index.try_clone()?;
+//! nightly.reset()?;
+//! nightly.add(&data)?;
+//!
+//! let ntotal = index.ntotal();
+//! index.merge_from(&mut nightly, ntotal)?;
+//! assert_eq!(index.ntotal(), 8);
+//! assert_eq!(nightly.ntotal(), 0);
+//! assert_eq!(index.list_size(0) + index.list_size(1), 8);
+//! # Ok(())
+//! # }
+//! # run().unwrap();
+//! ```
+
+use super::*;
+
+use std::os::raw::c_int;
+
+/// Trait for native indexes based on inverted lists (IVF).
+pub trait IvfIndex: Index + NativeIndex {
+    /// The type of the coarse quantizer,
+    /// or `IndexImpl` if it is not known.
+    type Quantizer;
+
+    /// Get number of inverted lists
+    fn nlist(&self) -> u32;
+
+    /// Get number of probes at query time
+    fn nprobe(&self) -> u32;
+
+    /// Set number of probes at query time
+    fn set_nprobe(&mut self, value: u32);
+
+    /// Get a view of the coarse quantizer,
+    /// which assigns vectors to the inverted lists.
+    ///
+    /// Once the index is trained, the quantizer holds the centroids
+    /// of the lists. It can only be searched if its type
+    /// supports concurrent searches.
+    fn quantizer(&self) -> IndexRef<'_, Self::Quantizer>;
+
+    /// Move all entries of `other` into this index, leaving `other` empty.
+    ///
+    /// `add_id` is added to all moved IDs. For sequential IDs, this would
+    /// be the number of vectors in this index before merging. Both indexes
+    /// must be of the same type, with the same quantizer.
+    fn merge_from<I>(&mut self, other: &mut I, add_id: u64) -> Result<()>
+    where
+        I: IvfIndex;
+
+    /// Copy a subset of the entries of this index to `other`.
+    ///
+    /// Both indexes must be of the same type, with the same quantizer.
+    fn copy_subset_to<I>(&self, other: &mut I, subset: SubsetType) -> Result<()>
+    where
+        I: IvfIndex;
+
+    /// Search the index with coarse assignments computed beforehand.
+    ///
+    /// For each query, `assign` contains the [`nprobe`] inverted lists to
+    /// visit, and `centroid_dis` the distances from the query to the
+    /// centroids of those lists, as obtained by searching the quantizer.
+    /// This makes it possible to run the coarse quantization elsewhere, and
+    /// to reuse it for several indexes sharing the same quantizer.
+    ///
+    /// Fails with [`Error::BadDimension`] if the length of `query` is not a
+    /// multiple of the index dimensionality, or if `assign` or `centroid_dis`
+    /// do not have `nprobe` entries per query.
+    ///
+    /// [`nprobe`]: #tymethod.nprobe
+    /// [`Error::BadDimension`]: ../../error/enum.Error.html#variant.BadDimension
+    fn search_preassigned(
+        &self,
+        query: &[f32],
+        k: usize,
+        assign: &[Idx],
+        centroid_dis: &[f32],
+    ) -> Result<SearchResult>;
+
+    /// Get the number of entries in the inverted list `list_no`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `list_no` is not lower than the number of lists.
+    fn list_size(&self, list_no: usize) -> usize;
+
+    /// Get the IDs of the entries in the inverted list `list_no`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `list_no` is not lower than the number of lists.
+    fn list_ids(&self, list_no: usize) -> Vec<Idx>;
+
+    /// Create the direct map from IDs to list entries if `enabled` is true,
+    /// or clear it otherwise.
+    ///
+    /// The direct map is needed to reconstruct vectors by ID.
+    fn make_direct_map(&mut self, enabled: bool) -> Result<()>;
+
+    /// Get the imbalance factor of the inverted lists,
+    /// which is 1 if they are perfectly balanced and greater otherwise.
+    fn imbalance_factor(&self) -> f64;
+
+    /// Print some statistics about the inverted lists to the standard output.
+    fn print_stats(&self);
+}
+
+/// Description of a subset of the entries of an IVF index,
+/// as used by [`copy_subset_to`].
+///
+/// [`copy_subset_to`]: trait.IvfIndex.html#tymethod.copy_subset_to
+#[derive(Debug, Copy, Clone, Eq, Hash, PartialEq)]
+pub enum SubsetType {
+    /// Entries with an ID in the range `start..end`
+    IdRange { start: u64, end: u64 },
+    /// Entries with an ID such that `id % modulus == remainder`
+    IdModulo { modulus: u64, remainder: u64 },
+    /// Entries of each list, leaving out the first `before` and
+    /// the last `after` entries of the whole index
+    ListRange { before: u64, after: u64 },
+}
+
+impl Default for SubsetType {
+    /// All entries of the index.
+    fn default() -> Self {
+        SubsetType::IdRange {
+            start: 0,
+            end: i64::MAX as u64,
+        }
+    }
+}
+
+impl SubsetType {
+    pub(crate) fn to_raw(self) -> (c_int, idx_t, idx_t) {
+        match self {
+            SubsetType::IdRange { start, end } => (0, start as idx_t, end as idx_t),
+            SubsetType::IdModulo { modulus, remainder } => {
+                (1, modulus as idx_t, remainder as idx_t)
+            }
+            SubsetType::ListRange { before, after } => (2, before as idx_t, after as idx_t),
+        }
+    }
+}
+
+/// Snapshot of the global search statistics of IVF indexes.
+///
+/// Faiss accumulates these statistics over the searches of all IVF indexes
+/// in the process. Searches running concurrently on other threads are also
+/// accounted for, so measurements should be taken while no other IVF
+/// searches are in progress.
+///
+/// # Examples
+///
+/// ```
+/// use faiss::{index_factory, Index, MetricType};
+/// use faiss::index::ivf::IvfStats;
+/// # fn run() -> Result<(), Box<dyn std::error::Error>> {
+/// let data = [0., 0., 0., 1., 10., 10., 10., 11.];
+/// let mut index = index_factory(2, "IVF2,Flat", MetricType::L2)?;
+/// index.train(&data)?;
+/// index.add(&data)?;
+///
+/// let (result, stats) = IvfStats::measure(|| index.search(&[0., 0.], 1));
+/// assert_eq!(result?.labels.len(), 1);
+/// assert_eq!(stats.nq, 1);
+/// # Ok(())
+/// # }
+/// # run().unwrap();
+/// ```
+#[derive(Debug, Copy, Clone, Default, PartialEq)]
+pub struct IvfStats {
+    /// Number of queries run
+    pub nq: usize,
+    /// Number of inverted lists scanned
+    pub nlist: usize,
+    /// Number of distances computed
+    pub ndis: usize,
+    /// Number of times the result heaps were updated
+    pub nheap_updates: usize,
+    /// Time spent in the coarse quantizer, in milliseconds
+    pub quantization_time: f64,
+    /// Time spent scanning the inverted lists, in milliseconds
+    pub search_time: f64,
+}
+
+impl IvfStats {
+    /// Take a snapshot of the global statistics.
+    pub fn get() -> Self {
+        unsafe { IvfStats::from(*faiss_get_indexIVF_stats()) }
+    }
+
+    /// Reset the global statistics to zero.
+    pub fn reset() {
+        unsafe {
+            faiss_IndexIVFStats_reset(faiss_get_indexIVF_stats());
+        }
+    }
+
+    /// Run the given function,
+    /// and obtain the statistics accumulated while it ran.
+    ///
+    /// The global statistics are not reset.
+    pub fn measure<F, T>(f: F) -> (T, IvfStats)
+    where
+        F: FnOnce() -> T,
+    {
+        let before = IvfStats::get();
+        let out = f();
+        let after = IvfStats::get();
+        (out, after.since(&before))
+    }
+
+    fn since(&self, before: &IvfStats) -> IvfStats {
+        IvfStats {
+            nq: self.nq.saturating_sub(before.nq),
+            nlist: self.nlist.saturating_sub(before.nlist),
+            ndis: self.ndis.saturating_sub(before.ndis),
+            nheap_updates: self.nheap_updates.saturating_sub(before.nheap_updates),
+            quantization_time: (self.quantization_time - before.quantization_time).max(0.),
+            search_time: (self.search_time - before.search_time).max(0.),
+        }
+    }
+}
+
+impl From<FaissIndexIVFStats> for IvfStats {
+    fn from(stats: FaissIndexIVFStats) -> Self {
+        IvfStats {
+            nq: stats.nq,
+            nlist: stats.nlist,
+            ndis: stats.ndis,
+            nheap_updates: stats.nheap_updates,
+            quantization_time: stats.quantization_time,
+            search_time: stats.search_time,
+        }
+    }
+}
+
+/// Alias for the native implementation of an IVF index.
+pub type IVFIndex = IVFIndexImpl;
+
+/// Native implementation of an IVF index of any kind.
+#[derive(Debug)]
+pub struct IVFIndexImpl {
+    inner: *mut FaissIndexIVF,
+}
+
+unsafe impl Send for IVFIndexImpl {}
+unsafe impl Sync for IVFIndexImpl {}
+
+impl CpuIndex for IVFIndexImpl {}
+
+impl Drop for IVFIndexImpl {
+    fn drop(&mut self) {
+        unsafe {
+            faiss_IndexIVF_free(self.inner);
+        }
+    }
+}
+
+impl NativeIndex for IVFIndexImpl {
+    fn inner_ptr(&self) -> *mut FaissIndex {
+        self.inner
+    }
+}
+
+impl FromInnerPtr for IVFIndexImpl {
+    unsafe fn from_inner_ptr(inner_ptr: *mut FaissIndex) -> Self {
+        IVFIndexImpl {
+            inner: inner_ptr as *mut FaissIndexIVF,
+        }
+    }
+}
+
+impl_index!(IVFIndexImpl);
+impl_concurrent_index!(IVFIndexImpl);
+impl_native_ivf_index!(IVFIndexImpl, IndexImpl);
+
+impl TryClone for IVFIndexImpl {
+    fn try_clone(&self) -> Result<Self>
+    where
+        Self: Sized,
+    {
+        try_clone_from_inner_ptr(self)
+    }
+}
+
+impl IndexImpl {
+    /// Attempt a dynamic cast of an index to the IVF index type.
+    pub fn into_ivf(self) -> Result<IVFIndexImpl> {
+        unsafe {
+            let new_inner = faiss_IndexIVF_cast(self.inner_ptr());
+            if new_inner.is_null() {
+                Err(Error::BadCast)
+            } else {
+                mem::forget(self);
+                Ok(IVFIndexImpl { inner: new_inner })
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{IvfIndex, IvfStats, SubsetType};
+    use crate::error::Error;
+    use crate::index::flat::FlatIndexImpl;
+    use crate::index::ivf_flat::IVFFlatIndexImpl;
+    use crate::index::{index_factory, Idx, Index, TryClone};
+    use crate::MetricType;
+
+    const D: u32 = 8;
+
+    fn some_data() -> Vec<f32> {
+        (0..16 * D as usize)
+            .map(|i| {
+                let row = i / D as usize;
+                let base = if row < 8 { 0. } else { 100. };
+                base + row as f32 * 0.5 + (i % D as usize) as f32 * 0.1
+            })
+            .collect()
+    }
+
+    #[test]
+    fn ivf_list_inspection() {
+        let data = some_data();
+        let mut index = index_factory(D, "IVF2,Flat", MetricType::L2)
+            .unwrap()
+            .into_ivf()
+            .unwrap();
+        index.train(&data).unwrap();
+        index.add(&data).unwrap();
+        assert_eq!(index.nlist(), 2);
+
+        assert_eq!(index.list_size(0), 8);
+        assert_eq!(index.list_size(1), 8);
+        assert_eq!(index.imbalance_factor(), 1.);
+        let mut ids: Vec<_> = (0..2)
+            .flat_map(|l| index.list_ids(l))
+            .map(|id| id.get().unwrap())
+            .collect();
+        ids.sort_unstable();
+        assert_eq!(ids, (0..16).collect::<Vec<_>>());
+
+        index.make_direct_map(true).unwrap();
+        index.make_direct_map(false).unwrap();
+    }
+
+    #[test]
+    fn ivf_merge_and_copy_subset() {
+        let data = some_data();
+        let mut index = index_factory(D, "IVF2,Flat", MetricType::L2)
+            .unwrap()
+            .into_ivf()
+            .unwrap();
+        index.train(&data).unwrap();
+        index.add(&data).unwrap();
+
+        let mut other = index.try_clone().unwrap();
+        other.reset().unwrap();
+        index
+            .copy_subset_to(&mut other, SubsetType::IdRange { start: 0, end: 4 })
+            .unwrap();
+        assert_eq!(other.ntotal(), 4);
+
+        index.merge_from(&mut other, 16).unwrap();
+        assert_eq!(index.ntotal(), 20);
+        assert_eq!(other.ntotal(), 0);
+        let result = index.search(&data[..D as usize], 2).unwrap();
+        let mut labels: Vec<_> = result.labels.iter().map(|l| l.get().unwrap()).collect();
+        labels.sort_unstable();
+        assert_eq!(labels, vec![0, 16]);
+    }
+
+    #[test]
+    fn ivf_bad_cast() {
+        let index = index_factory(D, "Flat", MetricType::L2).unwrap();
+        assert!(matches!(index.into_ivf(), Err(Error::BadCast)));
+    }
+
+    #[test]
+    fn ivf_quantizer() {
+        let data = some_data();
+        let q = FlatIndexImpl::new_l2(D).unwrap();
+        let mut index = IVFFlatIndexImpl::new_l2(q, D, 2).unwrap();
+        assert_eq!(index.quantizer().ntotal(), 0);
+        index.train(&data).unwrap();
+        index.add(&data).unwrap();
+
+        let quantizer = index.quantizer();
+        assert!(quantizer.is_trained());
+        assert_eq!(quantizer.d(), D);
+        assert_eq!(quantizer.ntotal(), 2);
+        let mut centroids = vec![0.; 2 * D as usize];
+        quantizer
+            .reconstruct_n(Idx::new(0), 2, &mut centroids)
+            .unwrap();
+
+        // each vector lands in the list of its closest centroid
+        let assigned = quantizer.assign(&data[..D as usize], 1).unwrap();
+        let list_no = assigned.labels[0].get().unwrap() as usize;
+        assert!(index.list_ids(list_no).contains(&Idx::new(0)));
+
+        let owned = quantizer.try_clone().unwrap();
+        assert_eq!(owned.ntotal(), 2);
+    }
+
+    #[test]
+    fn ivf_stats_measure() {
+        let data = some_data();
+        let mut index = index_factory(D, "IVF2,Flat", MetricType::L2)
+            .unwrap()
+            .into_ivf()
+            .unwrap();
+        index.train(&data).unwrap();
+        index.add(&data).unwrap();
+        index.set_nprobe(2);
+
+        let (result, stats) = IvfStats::measure(|| index.search(&data[..2 * D as usize], 1));
+        result.unwrap();
+        // other tests may be searching concurrently
+        assert!(stats.nq >= 2);
+        assert!(stats.nlist >= 4);
+        assert!(stats.ndis >= 32);
+    }
+
+    #[test]
+    fn ivf_search_preassigned() {
+        let data = some_data();
+        let mut index = index_factory(D, "IVF2,Flat", MetricType::L2)
+            .unwrap()
+            .into_ivf()
+            .unwrap();
+        index.train(&data).unwrap();
+        index.add(&data).unwrap();
+        index.set_nprobe(2);
+
+        // visiting every list is the same as an exhaustive search
+        let query = &data[..2 * D as usize];
+        let assign = [Idx::new(0), Idx::new(1), Idx::new(1), Idx::new(0)];
+        let result = index
+            .search_preassigned(query, 3, &assign, &[0.; 4])
+            .unwrap();
+        assert_eq!(result, index.search(query, 3).unwrap());
+
+        assert_eq!(
+            index.search_preassigned(query, 3, &assign[..2], &[0.; 2]),
+            Err(Error::BadDimension)
+        );
+        assert_eq!(
+            index.search_preassigned(&query[1..], 3, &assign, &[0.; 4]),
+            Err(Error::BadDimension)
+        );
+    }
+}
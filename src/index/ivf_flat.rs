@@ -2,23 +2,32 @@
 
 use super::*;
 
+use std::marker::PhantomData;
 use std::os::raw::{c_char, c_int};
 
-/// Alias for the native implementation of a flat index.
-pub type IVFFlatIndex = IVFFlatIndexImpl;
-
-/// Native implementation of a flat index.
+/// Alias for the native implementation of an IVF flat index.
+pub type IVFFlatIndex<Q = flat::FlatIndex> = IVFFlatIndexImpl<Q>;
+
+/// Native implementation of an IVF flat index.
+///
+/// The type parameter `Q` is the type of the coarse quantizer, which is
+/// owned by the index, and a flat index by default. Indexes of unknown origin,
+/// such as those obtained with [`IndexImpl::into_ivf_flat`], have a quantizer
+/// of type `IndexImpl`.
+///
+/// [`IndexImpl::into_ivf_flat`]: ../struct.IndexImpl.html#method.into_ivf_flat
 #[derive(Debug)]
-pub struct IVFFlatIndexImpl {
+pub struct IVFFlatIndexImpl<Q = flat::FlatIndex> {
     inner: *mut FaissIndexIVFFlat,
+    quantizer: PhantomData<Q>,
 }
 
-unsafe impl Send for IVFFlatIndexImpl {}
-unsafe impl Sync for IVFFlatIndexImpl {}
+unsafe impl<Q: Send> Send for IVFFlatIndexImpl<Q> {}
+unsafe impl<Q: Sync> Sync for IVFFlatIndexImpl<Q> {}
 
-impl CpuIndex for IVFFlatIndexImpl {}
+impl<Q: CpuIndex> CpuIndex for IVFFlatIndexImpl<Q> {}
 
-impl Drop for IVFFlatIndexImpl {
+impl<Q> Drop for IVFFlatIndexImpl<Q> {
     fn drop(&mut self) {
         unsafe {
             faiss_IndexIVFFlat_free(self.inner);
@@ -26,9 +35,12 @@ impl Drop for IVFFlatIndexImpl {
     }
 }
 
-impl IVFFlatIndexImpl {
+impl<Q> IVFFlatIndexImpl<Q>
+where
+    Q: NativeIndex,
+{
     fn new_helper(
-        quantizer: &flat::FlatIndex,
+        quantizer: &Q,
         d: u32,
         nlist: u32,
         metric: MetricType,
@@ -45,13 +57,16 @@ impl IVFFlatIndexImpl {
                 metric,
             ))?;
             faiss_IndexIVFFlat_set_own_fields(inner, c_int::from(own_fields));
-            Ok(IVFFlatIndexImpl { inner })
+            Ok(IVFFlatIndexImpl {
+                inner,
+                quantizer: PhantomData,
+            })
         }
     }
 
     /// Create a new IVF flat index.
     // The index owns the quantizer.
-    pub fn new(quantizer: flat::FlatIndex, d: u32, nlist: u32, metric: MetricType) -> Result<Self> {
+    pub fn new(quantizer: Q, d: u32, nlist: u32, metric: MetricType) -> Result<Self> {
         let index = IVFFlatIndexImpl::new_helper(&quantizer, d, nlist, metric, true)?;
         std::mem::forget(quantizer);
 
@@ -60,22 +75,29 @@ impl IVFFlatIndexImpl {
 
     /// Create a new IVF flat index with L2 as the metric type.
     // The index owns the quantizer.
-    pub fn new_l2(quantizer: flat::FlatIndex, d: u32, nlist: u32) -> Result<Self> {
+    pub fn new_l2(quantizer: Q, d: u32, nlist: u32) -> Result<Self> {
         IVFFlatIndexImpl::new(quantizer, d, nlist, MetricType::L2)
     }
 
     /// Create a new IVF flat index with IP (inner product) as the metric type.
     // The index owns the quantizer.
-    pub fn new_ip(quantizer: flat::FlatIndex, d: u32, nlist: u32) -> Result<Self> {
+    pub fn new_ip(quantizer: Q, d: u32, nlist: u32) -> Result<Self> {
         IVFFlatIndexImpl::new(quantizer, d, nlist, MetricType::InnerProduct)
     }
+}
 
+impl<Q> IVFFlatIndexImpl<Q> {
     /// Get number of probes at query time
     pub fn nprobe(&self) -> u32 {
         unsafe { faiss_IndexIVFFlat_nprobe(self.inner_ptr()) as u32 }
     }
 
-    /// Set number of probes at query time
+    /// Set number of probes at query time.
+    ///
+    /// See [`SearchParametersIvf`] for setting the number of probes
+    /// of a single search instead.
+    ///
+    /// [`SearchParametersIvf`]: ../../search_params/struct.SearchParametersIvf.html
     pub fn set_nprobe(&mut self, value: u32) {
         unsafe {
             faiss_IndexIVFFlat_set_nprobe(self.inner_ptr(), value as usize);
@@ -87,6 +109,33 @@ impl IVFFlatIndexImpl {
         unsafe { faiss_IndexIVFFlat_nlist(self.inner_ptr()) as u32 }
     }
 
+    /// Replace the stored vectors of the given IDs.
+    ///
+    /// The new vectors are assigned through the quantizer, so an entry is
+    /// moved to another inverted list if its new vector is closer to a
+    /// different centroid. The index must have a direct map, as created by
+    /// [`make_direct_map`].
+    ///
+    /// Fails with [`Error::BadDimension`] if the length of `xs` is not
+    /// the number of IDs times the index dimensionality.
+    ///
+    /// [`make_direct_map`]: ../ivf/trait.IvfIndex.html#tymethod.make_direct_map
+    /// [`Error::BadDimension`]: ../../error/enum.Error.html#variant.BadDimension
+    pub fn update_vectors(&mut self, ids: &[Idx], xs: &[f32]) -> Result<()> {
+        if xs.len() != ids.len() * self.d() as usize || ids.len() > c_int::MAX as usize {
+            return Err(Error::BadDimension);
+        }
+        unsafe {
+            faiss_try(faiss_IndexIVFFlat_update_vectors(
+                self.inner_ptr(),
+                ids.len() as c_int,
+                ids.as_ptr() as *mut _,
+                xs.as_ptr(),
+            ))?;
+            Ok(())
+        }
+    }
+
     /// Get train type
     pub fn train_type(&self) -> Option<TrainType> {
         unsafe {
@@ -122,24 +171,27 @@ impl TrainType {
     }
 }
 
-impl NativeIndex for IVFFlatIndexImpl {
+impl<Q> NativeIndex for IVFFlatIndexImpl<Q> {
     fn inner_ptr(&self) -> *mut FaissIndex {
         self.inner
     }
 }
 
-impl FromInnerPtr for IVFFlatIndexImpl {
+/// The quantizer of the native index is assumed to be of type `Q`.
+impl<Q> FromInnerPtr for IVFFlatIndexImpl<Q> {
     unsafe fn from_inner_ptr(inner_ptr: *mut FaissIndex) -> Self {
         IVFFlatIndexImpl {
             inner: inner_ptr as *mut FaissIndexIVFFlat,
+            quantizer: PhantomData,
         }
     }
 }
 
-impl_index!(IVFFlatIndex);
-impl_concurrent_index!(IVFFlatIndexImpl);
+impl_index!(IVFFlatIndexImpl<Q>, Q);
+impl_concurrent_index!(IVFFlatIndexImpl<Q>, Q: ConcurrentIndex);
+impl_native_ivf_index!(IVFFlatIndexImpl<Q>, Q, Q);
 
-impl TryClone for IVFFlatIndexImpl {
+impl<Q> TryClone for IVFFlatIndexImpl<Q> {
     fn try_clone(&self) -> Result<Self>
     where
         Self: Sized,
@@ -150,14 +202,17 @@ impl TryClone for IVFFlatIndexImpl {
 
 impl IndexImpl {
     /// Attempt a dynamic cast of an index to the IVF flat index type.
-    pub fn into_ivf_flat(self) -> Result<IVFFlatIndexImpl> {
+    pub fn into_ivf_flat(self) -> Result<IVFFlatIndexImpl<IndexImpl>> {
         unsafe {
             let new_inner = faiss_IndexIVFFlat_cast(self.inner_ptr());
             if new_inner.is_null() {
                 Err(Error::BadCast)
             } else {
                 mem::forget(self);
-                Ok(IVFFlatIndexImpl { inner: new_inner })
+                Ok(IVFFlatIndexImpl {
+                    inner: new_inner,
+                    quantizer: PhantomData,
+                })
             }
         }
     }
@@ -167,8 +222,14 @@ impl IndexImpl {
 mod tests {
 
     use super::IVFFlatIndexImpl;
+    use crate::error::Error;
     use crate::index::flat::FlatIndexImpl;
-    use crate::index::{index_factory, ConcurrentIndex, Idx, Index, UpcastIndex};
+    use crate::index::ivf::IvfIndex;
+    use crate::index::{
+        index_factory, ConcurrentIndex, Idx, Index, IndexImpl, TryClone, UpcastIndex,
+    };
+    use crate::search_params::SearchParametersIvf;
+    use crate::selector::IdSelector;
     use crate::MetricType;
 
     const D: u32 = 8;
@@ -242,6 +303,34 @@ mod tests {
         assert_eq!(index.ntotal(), 0);
     }
 
+    #[test]
+    fn index_search_with_ivf_params() {
+        let q = FlatIndexImpl::new_l2(D).unwrap();
+        let mut index = IVFFlatIndexImpl::new_l2(q, D, 1).unwrap();
+        let some_data = &[
+            7.5_f32, -7.5, 7.5, -7.5, 7.5, 7.5, 7.5, 7.5, -1., 1., 1., 1., 1., 1., 1., -1., 4.,
+            -4., -8., 1., 1., 2., 4., -1., 8., 8., 10., -10., -10., 10., -10., 10., 16., 16., 32.,
+            25., 20., 20., 40., 15.,
+        ];
+        index.train(some_data).unwrap();
+        index.add(some_data).unwrap();
+        assert_eq!(index.ntotal(), 5);
+
+        let params = SearchParametersIvf::new_with(None, 1, 0).unwrap();
+        let my_query = [0.; D as usize];
+        // nprobe is given per query, so an immutable ref suffices
+        let result = ConcurrentIndex::search_with_params(&index, &my_query, 3, &params).unwrap();
+        assert_eq!(result.labels.len(), 3);
+        assert!(result.labels.into_iter().all(Idx::is_some));
+        assert_eq!(index.nprobe(), 1);
+
+        let sel = IdSelector::batch(&[Idx::new(1), Idx::new(4)]).unwrap();
+        let params = SearchParametersIvf::new_with(Some(&sel), 1, 0).unwrap();
+        let result = index.search_with_params(&my_query, 3, &params).unwrap();
+        assert_eq!(&result.labels[..2], &[Idx::new(1), Idx::new(4)]);
+        assert!(result.labels[2].is_none());
+    }
+
     #[test]
     fn index_assign() {
         let q = FlatIndexImpl::new_l2(D).unwrap();
@@ -284,11 +373,109 @@ mod tests {
         index.add(some_data).unwrap();
         assert_eq!(index.ntotal(), 5);
 
-        let index: IVFFlatIndexImpl = index.into_ivf_flat().unwrap();
+        let index: IVFFlatIndexImpl<IndexImpl> = index.into_ivf_flat().unwrap();
         assert_eq!(index.is_trained(), true);
         assert_eq!(index.ntotal(), 5);
     }
 
+    #[test]
+    fn ivf_flat_factory_search_with_ivf_params() {
+        let mut index = index_factory(8, "IVF1,Flat", MetricType::L2).unwrap();
+        let some_data = &[
+            7.5_f32, -7.5, 7.5, -7.5, 7.5, 7.5, 7.5, 7.5, -1., 1., 1., 1., 1., 1., 1., -1., 0., 0.,
+            0., 1., 1., 0., 0., -1., 100., 100., 100., 100., -100., 100., 100., 100., 120., 100.,
+            100., 105., -100., 100., 100., 105.,
+        ];
+        index.train(some_data).unwrap();
+        index.add(some_data).unwrap();
+
+        let sel = IdSelector::range(Idx::new(3), Idx::new(5)).unwrap();
+        let params = SearchParametersIvf::new_with(Some(&sel), 1, 0).unwrap();
+        let my_query = [0.; 8];
+        let result = index.search_with_params(&my_query, 2, &params).unwrap();
+        assert_eq!(result.labels, vec![Idx::new(3), Idx::new(4)]);
+    }
+
+    #[test]
+    fn ivf_flat_update_vectors() {
+        let q = FlatIndexImpl::new_l2(D).unwrap();
+        let mut index = IVFFlatIndexImpl::new_l2(q, D, 1).unwrap();
+        let some_data = &[
+            7.5_f32, -7.5, 7.5, -7.5, 7.5, 7.5, 7.5, 7.5, -1., 1., 1., 1., 1., 1., 1., -1., 0., 0.,
+            0., 1., 1., 0., 0., -1., 100., 100., 100., 100., -100., 100., 100., 100., 120., 100.,
+            100., 105., -100., 100., 100., 105.,
+        ];
+        index.train(some_data).unwrap();
+        index.add(some_data).unwrap();
+        index.make_direct_map(true).unwrap();
+
+        let my_query = [50.; D as usize];
+        index.update_vectors(&[Idx::new(1)], &my_query).unwrap();
+        assert_eq!(index.ntotal(), 5);
+        let result = index.search(&my_query, 1).unwrap();
+        assert_eq!(result.labels, vec![Idx::new(1)]);
+        assert_eq!(result.distances, vec![0.]);
+
+        assert_eq!(
+            index.update_vectors(&[Idx::new(1), Idx::new(2)], &my_query),
+            Err(Error::BadDimension)
+        );
+    }
+
+    #[test]
+    fn ivf_flat_update_vectors_moves_list() {
+        let q = FlatIndexImpl::new_l2(D).unwrap();
+        let mut index = IVFFlatIndexImpl::new_l2(q, D, 2).unwrap();
+        let some_data: Vec<f32> = (0..8)
+            .flat_map(|i| {
+                let base = if i < 4 { 0. } else { 100. };
+                (0..D).map(move |j| base + (i * j % 3) as f32)
+            })
+            .collect();
+        index.train(&some_data).unwrap();
+        index.add(&some_data).unwrap();
+        index.make_direct_map(true).unwrap();
+
+        let list_of = |index: &IVFFlatIndexImpl<FlatIndexImpl>, x: &[f32]| {
+            let assign = index.quantizer().assign(x, 1).unwrap();
+            assign.labels[0].get().unwrap() as usize
+        };
+        let old_list = list_of(&index, &some_data[..D as usize]);
+        let new_vector = [100.; D as usize];
+        let new_list = list_of(&index, &new_vector);
+        assert_ne!(old_list, new_list);
+
+        index.update_vectors(&[Idx::new(0)], &new_vector).unwrap();
+        assert_eq!(index.ntotal(), 8);
+        assert!(index.list_ids(new_list).contains(&Idx::new(0)));
+        assert!(!index.list_ids(old_list).contains(&Idx::new(0)));
+        assert_eq!(index.list_size(old_list), 3);
+        assert_eq!(index.list_size(new_list), 5);
+    }
+
+    #[test]
+    fn ivf_flat_with_hnsw_quantizer() {
+        let q = index_factory(D, "HNSW8", MetricType::L2).unwrap();
+        let mut index = IVFFlatIndexImpl::new_l2(q, D, 2).unwrap();
+        let some_data = &[
+            7.5_f32, -7.5, 7.5, -7.5, 7.5, 7.5, 7.5, 7.5, -1., 1., 1., 1., 1., 1., 1., -1., 0., 0.,
+            0., 1., 1., 0., 0., -1., 100., 100., 100., 100., -100., 100., 100., 100., 120., 100.,
+            100., 105., -100., 100., 100., 105.,
+        ];
+        index.train(some_data).unwrap();
+        index.add(some_data).unwrap();
+        assert_eq!(index.quantizer().ntotal(), 2);
+
+        index.set_nprobe(2);
+        let my_query = [100.; D as usize];
+        let result = index.search(&my_query, 2).unwrap();
+        assert_eq!(result.labels, vec![Idx::new(3), Idx::new(4)]);
+
+        // cloning the index clones its quantizer as well
+        let index = index.try_clone().unwrap();
+        assert_eq!(index.ntotal(), 5);
+    }
+
     #[test]
     fn index_upcast() {
         let q = FlatIndexImpl::new_l2(D).unwrap();
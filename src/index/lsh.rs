@@ -112,6 +112,7 @@ impl LshIndex {
 }
 
 impl_index!(LshIndex);
+impl_native_codec!(LshIndex);
 
 impl TryClone for LshIndex {
     fn try_clone(&self) -> Result<Self>
@@ -152,6 +153,7 @@ impl_concurrent_index!(LshIndex);
 mod tests {
     use super::LshIndex;
     use crate::error::Result;
+    use crate::index::codec::Codec;
     use crate::index::{index_factory, ConcurrentIndex, FromInnerPtr, Idx, Index, NativeIndex};
     use crate::metric::MetricType;
 
@@ -252,4 +254,20 @@ mod tests {
         assert!(index.is_trained());
         assert_eq!(index.ntotal(), 5);
     }
+
+    #[test]
+    fn index_codec() {
+        let mut index = LshIndex::new(D, 16).unwrap();
+        let some_data = &[
+            7.5_f32, -7.5, 7.5, -7.5, 7.5, 7.5, 7.5, 7.5, -1., 1., 1., 1., 1., 1., 1., -1., 4.,
+            -4., -8., 1., 1., 2., 4., -1., 8., 8., 10., -10., -10., 10., -10., 10., 16., 16., 32.,
+            25., 20., 20., 40., 15.,
+        ];
+        index.train(some_data).unwrap();
+        assert_eq!(Codec::code_size(&index).unwrap(), index.code_size());
+
+        let codes = index.encode(some_data).unwrap();
+        assert_eq!(codes.len(), 5 * index.code_size());
+        assert!(index.encode(&some_data[1..]).is_err());
+    }
 }
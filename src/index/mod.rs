@@ -13,24 +13,34 @@
 use crate::error::{Error, Result};
 use crate::faiss_try;
 use crate::metric::MetricType;
+use crate::search_params::SearchParameters;
 use crate::selector::IdSelector;
+use std::cmp::Ordering;
 use std::ffi::CString;
 use std::fmt::{self, Display, Formatter, Write};
+use std::marker::PhantomData;
 use std::os::raw::c_uint;
 use std::{mem, ptr};
 
 use faiss_sys::*;
 
 pub mod autotune;
+pub mod binary;
+pub mod codec;
+pub mod distance_computer;
 pub mod flat;
 pub mod id_map;
 pub mod io;
 pub mod io_flags;
+pub mod ivf;
 pub mod ivf_flat;
 pub mod lsh;
 pub mod pretransform;
 pub mod refine_flat;
+pub mod replicas;
 pub mod scalar_quantizer;
+pub mod shards;
+mod stream;
 
 #[cfg(feature = "gpu")]
 pub mod gpu;
@@ -170,6 +180,15 @@ pub trait Index {
     /// Perform a search for the `k` closest vectors to the given query vectors.
     fn search(&mut self, q: &[f32], k: usize) -> Result<SearchResult>;
 
+    /// Perform a search for the `k` closest vectors to the given query vectors,
+    /// with the given search-time parameters (such as an ID selector).
+    fn search_with_params(
+        &mut self,
+        q: &[f32],
+        k: usize,
+        params: &SearchParameters,
+    ) -> Result<SearchResult>;
+
     /// Perform a ranged search for the vectors closest to the given query vectors
     /// by the given radius.
     fn range_search(&mut self, q: &[f32], radius: f32) -> Result<RangeSearchResult>;
@@ -178,6 +197,19 @@ pub trait Index {
 
     fn reconstruct_n(&self, first_key: Idx, count: usize, output: &mut [f32]) -> Result<()>;
 
+    /// Compute the residual of a vector after encoding, which is the
+    /// difference between the vector and the reconstruction of the given key
+    /// (such as a coarse centroid obtained through `assign`).
+    ///
+    /// Fails with `Error::BadDimension` if the length of `x` is not `d`.
+    fn compute_residual(&self, x: &[f32], key: Idx) -> Result<Vec<f32>>;
+
+    /// Compute the residuals of a set of vectors, each against its own key.
+    ///
+    /// Fails with `Error::BadDimension` if the length of `xs`
+    /// is not `keys.len() * d`.
+    fn compute_residual_n(&self, xs: &[f32], keys: &[Idx]) -> Result<Vec<f32>>;
+
     /// Clear the entire index.
     fn reset(&mut self) -> Result<()>;
 
@@ -231,6 +263,15 @@ where
         (**self).search(q, k)
     }
 
+    fn search_with_params(
+        &mut self,
+        q: &[f32],
+        k: usize,
+        params: &SearchParameters,
+    ) -> Result<SearchResult> {
+        (**self).search_with_params(q, k, params)
+    }
+
     fn range_search(&mut self, q: &[f32], radius: f32) -> Result<RangeSearchResult> {
         (**self).range_search(q, radius)
     }
@@ -243,6 +284,14 @@ where
         (**self).reconstruct_n(first_key, count, output)
     }
 
+    fn compute_residual(&self, x: &[f32], key: Idx) -> Result<Vec<f32>> {
+        (**self).compute_residual(x, key)
+    }
+
+    fn compute_residual_n(&self, xs: &[f32], keys: &[Idx]) -> Result<Vec<f32>> {
+        (**self).compute_residual_n(xs, keys)
+    }
+
     fn reset(&mut self) -> Result<()> {
         (**self).reset()
     }
@@ -287,6 +336,15 @@ pub trait ConcurrentIndex: Index {
     /// Perform a search for the `k` closest vectors to the given query vectors.
     fn search(&self, q: &[f32], k: usize) -> Result<SearchResult>;
 
+    /// Perform a search for the `k` closest vectors to the given query vectors,
+    /// with the given search-time parameters (such as an ID selector).
+    fn search_with_params(
+        &self,
+        q: &[f32],
+        k: usize,
+        params: &SearchParameters,
+    ) -> Result<SearchResult>;
+
     /// Perform a ranged search for the vectors closest to the given query vectors
     /// by the given radius.
     fn range_search(&self, q: &[f32], radius: f32) -> Result<RangeSearchResult>;
@@ -301,6 +359,15 @@ impl<CI: ConcurrentIndex> ConcurrentIndex for Box<CI> {
         (**self).search(q, k)
     }
 
+    fn search_with_params(
+        &self,
+        q: &[f32],
+        k: usize,
+        params: &SearchParameters,
+    ) -> Result<SearchResult> {
+        (**self).search_with_params(q, k, params)
+    }
+
     fn range_search(&self, q: &[f32], radius: f32) -> Result<RangeSearchResult> {
         (**self).range_search(q, radius)
     }
@@ -385,7 +452,7 @@ pub struct SearchResult {
 }
 
 /// The outcome of an index range search operation.
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug)]
 pub struct RangeSearchResult {
     inner: *mut FaissRangeSearchResult,
 }
@@ -420,14 +487,14 @@ impl RangeSearchResult {
 
     /// getter for labels and respective distances (not sorted):
     /// result for query `i` is `labels[lims[i] .. lims[i+1]]`
-    pub fn distance_and_labels_mut(&self) -> (&mut [f32], &mut [Idx]) {
+    pub fn distance_and_labels_mut(&mut self) -> (&mut [f32], &mut [Idx]) {
+        let full_len = self.lims().last().cloned().unwrap_or(0);
         unsafe {
-            let buf_size = faiss_RangeSearchResult_buffer_size(self.inner);
             let mut distances_ptr = ptr::null_mut();
             let mut labels_ptr = ptr::null_mut();
             faiss_RangeSearchResult_labels(self.inner, &mut labels_ptr, &mut distances_ptr);
-            let distances = ::std::slice::from_raw_parts_mut(distances_ptr, buf_size);
-            let labels = ::std::slice::from_raw_parts_mut(labels_ptr as *mut Idx, buf_size);
+            let distances = ::std::slice::from_raw_parts_mut(distances_ptr, full_len);
+            let labels = ::std::slice::from_raw_parts_mut(labels_ptr as *mut Idx, full_len);
             (distances, labels)
         }
     }
@@ -455,6 +522,120 @@ impl RangeSearchResult {
     pub fn labels_mut(&mut self) -> &mut [Idx] {
         self.distance_and_labels_mut().1
     }
+
+    /// getter for the distances and labels of the results for query `i`
+    ///
+    /// # Panics
+    ///
+    /// Panics if `i` is not lower than the number of queries.
+    pub fn query(&self, i: usize) -> (&[f32], &[Idx]) {
+        let nq = self.nq();
+        assert!(i < nq, "query {} out of bounds ({} queries)", i, nq);
+        let lims = self.lims();
+        let (distances, labels) = self.distance_and_labels();
+        let range = lims[i]..lims[i + 1];
+        (&distances[range.clone()], &labels[range])
+    }
+
+    /// Iterate over the distances and labels of the results of each query.
+    pub fn iter(&self) -> RangeSearchResultIter<'_> {
+        let (distances, labels) = self.distance_and_labels();
+        RangeSearchResultIter {
+            lims: self.lims(),
+            distances,
+            labels,
+        }
+    }
+
+    /// Convert into a list of `(label, distance)` pairs for each query.
+    pub fn into_vecs(self) -> Vec<Vec<(Idx, f32)>> {
+        self.iter()
+            .map(|(distances, labels)| {
+                labels
+                    .iter()
+                    .cloned()
+                    .zip(distances.iter().cloned())
+                    .collect()
+            })
+            .collect()
+    }
+
+    /// Sort the results of each query by ascending distance,
+    /// so that the nearest vectors come first with the L2 metric.
+    pub fn sort_by_distance(&mut self) {
+        self.sort_by(|a, b| a.total_cmp(&b));
+    }
+
+    /// Sort the results of each query by descending distance,
+    /// so that the most similar vectors come first with the inner product metric.
+    pub fn sort_by_distance_desc(&mut self) {
+        self.sort_by(|a, b| b.total_cmp(&a));
+    }
+
+    fn sort_by<F>(&mut self, mut compare: F)
+    where
+        F: FnMut(f32, f32) -> Ordering,
+    {
+        let lims = self.lims().to_vec();
+        let (distances, labels) = self.distance_and_labels_mut();
+        let mut pairs = Vec::new();
+        for w in lims.windows(2) {
+            let distances = &mut distances[w[0]..w[1]];
+            let labels = &mut labels[w[0]..w[1]];
+            pairs.clear();
+            pairs.extend(distances.iter().cloned().zip(labels.iter().cloned()));
+            pairs.sort_by(|a, b| compare(a.0, b.0));
+            for (j, (dis, label)) in pairs.iter().enumerate() {
+                distances[j] = *dis;
+                labels[j] = *label;
+            }
+        }
+    }
+}
+
+/// Iterator over the results of each query in a range search.
+///
+/// See [`RangeSearchResult::iter`](struct.RangeSearchResult.html#method.iter).
+#[derive(Debug, Clone)]
+pub struct RangeSearchResultIter<'a> {
+    lims: &'a [usize],
+    distances: &'a [f32],
+    labels: &'a [Idx],
+}
+
+impl<'a> Iterator for RangeSearchResultIter<'a> {
+    type Item = (&'a [f32], &'a [Idx]);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.lims.len() < 2 {
+            return None;
+        }
+        let range = self.lims[0]..self.lims[1];
+        self.lims = &self.lims[1..];
+        Some((&self.distances[range.clone()], &self.labels[range]))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let n = self.lims.len().saturating_sub(1);
+        (n, Some(n))
+    }
+}
+
+impl ExactSizeIterator for RangeSearchResultIter<'_> {}
+
+impl<'a> IntoIterator for &'a RangeSearchResult {
+    type Item = (&'a [f32], &'a [Idx]);
+    type IntoIter = RangeSearchResultIter<'a>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl PartialEq for RangeSearchResult {
+    fn eq(&self, other: &Self) -> bool {
+        self.lims() == other.lims() && self.distance_and_labels() == other.distance_and_labels()
+    }
 }
 
 impl Drop for RangeSearchResult {
@@ -516,6 +697,106 @@ impl TryFromInnerPtr for IndexImpl {
     }
 }
 
+/// Borrowed view of a native index owned by another index,
+/// such as the coarse quantizer of an IVF index.
+///
+/// `Q` is the type of the borrowed index, if known.
+/// Only the operations which do not modify the index are available,
+/// and searching is only available if `Q` supports concurrent searches.
+/// Use [`try_clone`] to obtain an independent index.
+///
+/// [`try_clone`]: #method.try_clone
+#[derive(Debug)]
+pub struct IndexRef<'a, Q = IndexImpl> {
+    inner: *mut FaissIndex,
+    phantom: PhantomData<&'a Q>,
+}
+
+// as thread safe as a shared reference to the index
+unsafe impl<Q: Sync> Send for IndexRef<'_, Q> {}
+unsafe impl<Q: Sync> Sync for IndexRef<'_, Q> {}
+
+impl<Q> IndexRef<'_, Q> {
+    /// Create a view of the given native index.
+    ///
+    /// # Safety
+    ///
+    /// The pointer must refer to a valid index of type `Q`,
+    /// which must outlive the lifetime `'a`.
+    pub(crate) unsafe fn from_inner_ptr(inner: *mut FaissIndex) -> Self {
+        IndexRef {
+            inner,
+            phantom: PhantomData,
+        }
+    }
+
+    /// Obtain the raw pointer to the native index.
+    ///
+    /// The index is owned elsewhere, so it must not be freed.
+    pub fn inner_ptr(&self) -> *mut FaissIndex {
+        self.inner
+    }
+
+    /// Run an operation on a non-owning index value.
+    fn with<T>(&self, op: impl FnOnce(&mut IndexImpl) -> T) -> T {
+        let mut index = mem::ManuallyDrop::new(IndexImpl { inner: self.inner });
+        op(&mut index)
+    }
+
+    /// Whether the index does not require training, or if training is done already
+    pub fn is_trained(&self) -> bool {
+        self.with(|index| index.is_trained())
+    }
+
+    /// The total number of vectors indexed
+    pub fn ntotal(&self) -> u64 {
+        self.with(|index| index.ntotal())
+    }
+
+    /// The dimensionality of the indexed vectors
+    pub fn d(&self) -> u32 {
+        self.with(|index| index.d())
+    }
+
+    /// The metric type assumed by the index
+    pub fn metric_type(&self) -> MetricType {
+        self.with(|index| index.metric_type())
+    }
+
+    /// Reconstruct the stored vector with the given key.
+    pub fn reconstruct(&self, key: Idx, output: &mut [f32]) -> Result<()> {
+        self.with(|index| index.reconstruct(key, output))
+    }
+
+    /// Reconstruct `count` stored vectors, starting with the given key.
+    pub fn reconstruct_n(&self, first_key: Idx, count: usize, output: &mut [f32]) -> Result<()> {
+        self.with(|index| index.reconstruct_n(first_key, count, output))
+    }
+
+    /// Create an independent copy of the index.
+    pub fn try_clone(&self) -> Result<IndexImpl> {
+        self.with(|index| index.try_clone())
+    }
+}
+
+impl<Q: ConcurrentIndex> IndexRef<'_, Q> {
+    /// Similar to `search`, but only provides the labels.
+    pub fn assign(&self, q: &[f32], k: usize) -> Result<AssignSearchResult> {
+        self.with(|index| index.assign(q, k))
+    }
+
+    /// Perform a search for the `k` closest vectors to the given query vectors.
+    pub fn search(&self, q: &[f32], k: usize) -> Result<SearchResult> {
+        self.with(|index| index.search(q, k))
+    }
+
+    /// Perform a ranged search for the vectors closest to the given query vectors
+    /// by the given radius.
+    pub fn range_search(&self, q: &[f32], radius: f32) -> Result<RangeSearchResult> {
+        self.with(|index| index.range_search(q, radius))
+    }
+}
+
 /// Index upcast trait.
 ///
 /// If you need to store several different types of indexes in one collection,
@@ -547,6 +828,7 @@ impl<NI: NativeIndex> UpcastIndex for NI {
 }
 
 impl_index!(IndexImpl);
+impl_native_codec!(IndexImpl);
 
 impl TryClone for IndexImpl {
     fn try_clone(&self) -> Result<Self>
@@ -588,6 +870,8 @@ where
 mod tests {
     use super::{index_factory, Idx, Index, TryClone};
     use crate::metric::MetricType;
+    use crate::search_params::SearchParameters;
+    use crate::selector::IdSelector;
 
     #[test]
     fn index_factory_flat() {
@@ -768,6 +1052,34 @@ mod tests {
         assert_eq!(index.ntotal(), 0);
     }
 
+    #[test]
+    fn flat_index_search_with_params() {
+        let mut index = index_factory(8, "Flat", MetricType::L2).unwrap();
+        let some_data = &[
+            7.5_f32, -7.5, 7.5, -7.5, 7.5, 7.5, 7.5, 7.5, -1., 1., 1., 1., 1., 1., 1., -1., 0., 0.,
+            0., 1., 1., 0., 0., -1., 100., 100., 100., 100., -100., 100., 100., 100., 120., 100.,
+            100., 105., -100., 100., 100., 105.,
+        ];
+        index.add(some_data).unwrap();
+        assert_eq!(index.ntotal(), 5);
+
+        let my_query = [0.; 8];
+        let params = SearchParameters::new().unwrap();
+        let result = index.search_with_params(&my_query, 5, &params).unwrap();
+        assert_eq!(result.labels, vec![Idx(2), Idx(1), Idx(0), Idx(3), Idx(4)]);
+
+        let sel = IdSelector::batch(&[Idx(1), Idx(3)]).unwrap();
+        let params = SearchParameters::with_selector(&sel).unwrap();
+        let result = index.search_with_params(&my_query, 3, &params).unwrap();
+        assert_eq!(&result.labels[..2], &[Idx(1), Idx(3)]);
+        assert!(result.labels[2].is_none());
+
+        let sel = IdSelector::range(Idx(3), Idx(5)).unwrap();
+        let params = SearchParameters::with_selector(&sel).unwrap();
+        let result = index.search_with_params(&my_query, 1, &params).unwrap();
+        assert_eq!(result.labels, vec![Idx(3)]);
+    }
+
     #[test]
     fn flat_index_range_search() {
         let mut index = index_factory(8, "Flat", MetricType::L2).unwrap();
@@ -785,4 +1097,41 @@ mod tests {
         assert!(labels == &[Idx(1), Idx(2)] || labels == &[Idx(2), Idx(1)]);
         assert!(distances.iter().all(|x| *x > 0.));
     }
+
+    #[test]
+    fn range_search_result_per_query() {
+        let mut index = index_factory(2, "Flat", MetricType::L2).unwrap();
+        index.add(&[0., 0., 1., 0., 3., 0., 0., 2.]).unwrap();
+
+        let queries = [0., 0., 10., 10., 3., 0.];
+        let mut result = index.range_search(&queries, 4.5).unwrap();
+        assert_eq!(result.iter().len(), 3);
+        assert!(result.query(1).1.is_empty());
+
+        result.sort_by_distance();
+        let (distances, labels) = result.query(0);
+        assert_eq!(distances, &[0., 1., 4.]);
+        assert_eq!(labels, &[Idx(0), Idx(1), Idx(3)]);
+
+        result.sort_by_distance_desc();
+        assert_eq!(result.query(2).1, &[Idx(1), Idx(2)]);
+
+        let per_query: Vec<_> = result.iter().map(|(_, labels)| labels.len()).collect();
+        assert_eq!(per_query, vec![3, 0, 2]);
+
+        let mut other = index.range_search(&queries, 4.5).unwrap();
+        other.sort_by_distance();
+        assert_ne!(result, other);
+        other.sort_by_distance_desc();
+        assert_eq!(result, other);
+
+        assert_eq!(
+            result.into_vecs(),
+            vec![
+                vec![(Idx(3), 4.), (Idx(1), 1.), (Idx(0), 0.)],
+                vec![],
+                vec![(Idx(1), 4.), (Idx(2), 0.)],
+            ]
+        );
+    }
 }
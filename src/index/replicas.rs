@@ -0,0 +1,278 @@
+//! Module for the index replicas wrapper.
+//!
+//! A [`ReplicatedIndex`] holds several replicas of the same index, which are
+//! expected to contain the same vectors. Vectors added to the collection are
+//! added to every replica, whereas the queries of a search are split across
+//! the replicas, optionally in parallel. This improves the search throughput
+//! at the expense of memory.
+//!
+//! [`ReplicatedIndex`]: struct.ReplicatedIndex.html
+//!
+//! # Examples
+//!
+//! ```
+//! use faiss::{Idx, Index, FlatIndex};
+//! use faiss::index::replicas::ReplicatedIndex;
+//! # fn run() -> Result<(), Box<dyn std::error::Error>> {
+//! let mut index = FlatIndex::new_l2(2)?;
+//! index.add(&[0., 0., 1., 1., 2., 2.])?;
+//!
+//! let mut index = ReplicatedIndex::replicate(index, 2)?;
+//! assert_eq!(index.num_replicas(), 2);
+//! assert_eq!(index.ntotal(), 3);
+//!
+//! let result = index.search(&[0., 0., 2., 2.], 1)?;
+//! assert_eq!(result.labels, vec![Idx::new(0), Idx::new(2)]);
+//! # Ok(())
+//! # }
+//! # run().unwrap();
+//! ```
+
+use crate::error::{Error, Result};
+use crate::faiss_try;
+use crate::index::{
+    AssignSearchResult, ConcurrentIndex, CpuIndex, FromInnerPtr, Idx, Index, IndexRef, NativeIndex,
+    RangeSearchResult, SearchResult, TryClone,
+};
+use crate::selector::IdSelector;
+use faiss_sys::*;
+
+use std::marker::PhantomData;
+use std::mem;
+use std::os::raw::c_int;
+use std::ptr;
+
+/// Wrapper for searching through several replicas of an index of type `I`.
+///
+/// See the [module level documentation] for more information.
+///
+/// [module level documentation]: ./index.html
+#[derive(Debug)]
+pub struct ReplicatedIndex<I> {
+    inner: *mut FaissIndexReplicas,
+    num_replicas: usize,
+    phantom: PhantomData<I>,
+}
+
+unsafe impl<I: Send> Send for ReplicatedIndex<I> {}
+unsafe impl<I: Sync> Sync for ReplicatedIndex<I> {}
+impl<I: CpuIndex> CpuIndex for ReplicatedIndex<I> {}
+
+impl<I> NativeIndex for ReplicatedIndex<I> {
+    fn inner_ptr(&self) -> *mut FaissIndex {
+        self.inner
+    }
+}
+
+impl<I> Drop for ReplicatedIndex<I> {
+    fn drop(&mut self) {
+        unsafe {
+            faiss_IndexReplicas_free(self.inner);
+        }
+    }
+}
+
+impl<I> ReplicatedIndex<I>
+where
+    I: NativeIndex,
+{
+    /// Create a new, empty collection of replicas of dimensionality `d`.
+    ///
+    /// Each replica is searched in a separate thread.
+    pub fn new(d: u32) -> Result<Self> {
+        unsafe {
+            let mut inner = ptr::null_mut();
+            faiss_try(faiss_IndexReplicas_new(&mut inner, d as idx_t))?;
+            Ok(ReplicatedIndex::from_new_inner(inner))
+        }
+    }
+
+    /// Create a new, empty collection of replicas of dimensionality `d`.
+    ///
+    /// If `threaded` is true, each replica is searched in a separate thread.
+    pub fn new_with_options(d: u32, threaded: bool) -> Result<Self> {
+        unsafe {
+            let mut inner = ptr::null_mut();
+            faiss_try(faiss_IndexReplicas_new_with_options(
+                &mut inner,
+                d as idx_t,
+                c_int::from(threaded),
+            ))?;
+            Ok(ReplicatedIndex::from_new_inner(inner))
+        }
+    }
+
+    /// Create a threaded collection of `num_replicas` replicas of the given
+    /// index, consisting of the index itself and `num_replicas - 1` clones.
+    ///
+    /// Fails with `Error::BadSubIndex` if `num_replicas` is zero.
+    pub fn replicate(index: I, num_replicas: usize) -> Result<Self>
+    where
+        I: TryClone,
+    {
+        if num_replicas == 0 {
+            return Err(Error::BadSubIndex);
+        }
+        let mut replicas = ReplicatedIndex::new(index.d())?;
+        for _ in 1..num_replicas {
+            replicas.add_replica(index.try_clone()?)?;
+        }
+        replicas.add_replica(index)?;
+        Ok(replicas)
+    }
+
+    unsafe fn from_new_inner(inner: *mut FaissIndexReplicas) -> Self {
+        // let the replicas container take ownership of its replicas
+        faiss_IndexReplicas_set_own_fields(inner, 1);
+        ReplicatedIndex {
+            inner,
+            num_replicas: 0,
+            phantom: PhantomData,
+        }
+    }
+
+    /// Add a replica to the collection, which takes ownership of it.
+    ///
+    /// The replica must have the same dimensionality as the collection,
+    /// and should contain the same vectors as the other replicas.
+    pub fn add_replica(&mut self, replica: I) -> Result<()> {
+        unsafe {
+            faiss_try(faiss_IndexReplicas_add_replica(
+                self.inner,
+                replica.inner_ptr(),
+            ))?;
+        }
+        mem::forget(replica);
+        self.num_replicas += 1;
+        Ok(())
+    }
+
+    /// Remove the replica at position `i` from the collection,
+    /// giving back ownership of it.
+    ///
+    /// The replicas after it are shifted to the left.
+    pub fn remove_replica(&mut self, i: usize) -> Result<I>
+    where
+        I: FromInnerPtr,
+    {
+        let replica_inner = self.replica_inner_ptr(i).ok_or(Error::BadSubIndex)?;
+        unsafe {
+            // make the collection disown the replica while it is removed
+            faiss_IndexReplicas_set_own_fields(self.inner, 0);
+            let r = faiss_try(faiss_IndexReplicas_remove_replica(
+                self.inner,
+                replica_inner,
+            ));
+            faiss_IndexReplicas_set_own_fields(self.inner, 1);
+            r?;
+            self.num_replicas -= 1;
+            Ok(I::from_inner_ptr(replica_inner))
+        }
+    }
+
+    /// Obtain the number of replicas in the collection.
+    pub fn num_replicas(&self) -> usize {
+        self.num_replicas
+    }
+
+    /// Obtain the raw pointer to the replica at position `i`,
+    /// or `None` if there is no such replica.
+    ///
+    /// # Safety
+    ///
+    /// While this method is safe, note that the returned index pointer is
+    /// already owned by this collection. Therefore, it is undefined behavior
+    /// to create a high-level index value from this pointer. See
+    /// [`replica`] and [`remove_replica`] for safe alternatives.
+    ///
+    /// [`replica`]: #method.replica
+    /// [`remove_replica`]: #method.remove_replica
+    pub fn replica_inner_ptr(&self, i: usize) -> Option<*mut FaissIndex> {
+        if i >= self.num_replicas {
+            return None;
+        }
+        unsafe { Some(faiss_IndexReplicas_at(self.inner, i as c_int)) }
+    }
+
+    /// Obtain a view of the replica at position `i`,
+    /// or `None` if there is no such replica.
+    pub fn replica(&self, i: usize) -> Option<IndexRef<'_, I>> {
+        self.replica_inner_ptr(i)
+            .map(|inner| unsafe { IndexRef::from_inner_ptr(inner) })
+    }
+}
+
+impl_index!(ReplicatedIndex<I>, I);
+impl_concurrent_index!(ReplicatedIndex<I>, I: ConcurrentIndex);
+
+#[cfg(test)]
+mod tests {
+    use super::ReplicatedIndex;
+    use crate::error::Error;
+    use crate::index::{flat::FlatIndexImpl, ConcurrentIndex, Idx, Index};
+
+    const D: u32 = 8;
+
+    #[test]
+    fn replicated_index_search() {
+        let mut index = FlatIndexImpl::new_l2(D).unwrap();
+        let some_data = &[
+            7.5_f32, -7.5, 7.5, -7.5, 7.5, 7.5, 7.5, 7.5, -1., 1., 1., 1., 1., 1., 1., -1., 0., 0.,
+            0., 1., 1., 0., 0., -1., 100., 100., 100., 100., -100., 100., 100., 100., 120., 100.,
+            100., 105., -100., 100., 100., 105.,
+        ];
+        index.add(some_data).unwrap();
+
+        let index = ReplicatedIndex::replicate(index, 3).unwrap();
+        assert_eq!(index.num_replicas(), 3);
+        assert_eq!(index.ntotal(), 5);
+
+        let my_query = vec![
+            0., 0., 0., 0., 0., 0., 0., 0., 100., 100., 100., 100., 100., 100., 100., 100.,
+        ];
+        let result = index.search(&my_query, 5).unwrap();
+        assert_eq!(
+            result.labels,
+            vec![2, 1, 0, 3, 4, 3, 4, 0, 1, 2]
+                .into_iter()
+                .map(Idx::new)
+                .collect::<Vec<_>>()
+        );
+
+        let result = ConcurrentIndex::search(&index, &my_query, 1).unwrap();
+        assert_eq!(result.labels, vec![Idx::new(2), Idx::new(3)]);
+    }
+
+    #[test]
+    fn replicated_index_add_and_remove() {
+        let mut index = ReplicatedIndex::new_with_options(D, false).unwrap();
+        index
+            .add_replica(FlatIndexImpl::new_l2(D).unwrap())
+            .unwrap();
+        index
+            .add_replica(FlatIndexImpl::new_l2(D).unwrap())
+            .unwrap();
+
+        // vectors are added to every replica
+        index.add(&[1.; D as usize]).unwrap();
+        assert_eq!(index.ntotal(), 1);
+        let replica = index.replica(1).unwrap();
+        assert_eq!(replica.ntotal(), 1);
+        let result = replica.search(&[1.; D as usize], 1).unwrap();
+        assert_eq!(result.labels, vec![Idx::new(0)]);
+
+        let replica: FlatIndexImpl = index.remove_replica(1).unwrap();
+        assert_eq!(replica.ntotal(), 1);
+        assert_eq!(index.num_replicas(), 1);
+        assert!(index.replica_inner_ptr(1).is_none());
+        assert!(index.replica(1).is_none());
+        assert!(index.remove_replica(1).is_err());
+    }
+
+    #[test]
+    fn replicate_zero_replicas() {
+        let index = FlatIndexImpl::new_l2(D).unwrap();
+        let r = ReplicatedIndex::replicate(index, 0);
+        assert_eq!(r.err(), Some(Error::BadSubIndex));
+    }
+}
@@ -100,6 +100,7 @@ impl FromInnerPtr for ScalarQuantizerIndexImpl {
 }
 
 impl_index!(ScalarQuantizerIndexImpl);
+impl_native_codec!(ScalarQuantizerIndexImpl);
 
 impl TryClone for ScalarQuantizerIndexImpl {
     fn try_clone(&self) -> Result<Self>
@@ -251,7 +252,12 @@ where
         unsafe { faiss_IndexIVFScalarQuantizer_nprobe(self.inner_ptr()) as u32 }
     }
 
-    /// Set number of probes at query time
+    /// Set number of probes at query time.
+    ///
+    /// See [`SearchParametersIvf`] for setting the number of probes
+    /// of a single search instead.
+    ///
+    /// [`SearchParametersIvf`]: ../../search_params/struct.SearchParametersIvf.html
     pub fn set_nprobe(&mut self, value: u32) {
         unsafe {
             faiss_IndexIVFScalarQuantizer_set_nprobe(self.inner_ptr(), value as usize);
@@ -276,6 +282,7 @@ impl FromInnerPtr for IVFScalarQuantizerIndexImpl<IndexImpl> {
 
 impl_index!(IVFScalarQuantizerIndexImpl<Q>, Q);
 impl_concurrent_index!(IVFScalarQuantizerIndexImpl<Q>, Q: ConcurrentIndex);
+impl_native_ivf_index!(IVFScalarQuantizerIndexImpl<Q>, Q, Q);
 
 impl<Q> TryClone for IVFScalarQuantizerIndexImpl<Q> {
     fn try_clone(&self) -> Result<Self>
@@ -314,8 +321,11 @@ impl IndexImpl {
 #[cfg(test)]
 mod tests {
     use super::{IVFScalarQuantizerIndexImpl, QuantizerType, ScalarQuantizerIndexImpl};
+    use crate::index::codec::Codec;
     use crate::index::{flat, index_factory, ConcurrentIndex, Idx, Index, UpcastIndex};
     use crate::metric::MetricType;
+    use crate::search_params::SearchParametersIvf;
+    use crate::selector::IdSelector;
 
     const D: u32 = 8;
 
@@ -426,6 +436,28 @@ mod tests {
         assert_eq!(index.ntotal(), 0);
     }
 
+    #[test]
+    fn ivf_sq_index_search_with_ivf_params() {
+        let quantizer = flat::FlatIndex::new_l2(D).unwrap();
+        let mut index =
+            IVFScalarQuantizerIndexImpl::new_l2(quantizer, D, QuantizerType::QT_fp16, 1).unwrap();
+        let some_data = &[
+            7.5_f32, -7.5, 7.5, -7.5, 7.5, 7.5, 7.5, 7.5, -1., 1., 1., 1., 1., 1., 1., -1., 0., 0.,
+            0., 1., 1., 0., 0., -1., 100., 100., 100., 100., -100., 100., 100., 100., 120., 100.,
+            100., 105., -100., 100., 100., 105.,
+        ];
+        index.train(some_data).unwrap();
+        index.add(some_data).unwrap();
+        assert_eq!(index.ntotal(), 5);
+
+        let sel = IdSelector::batch(&[Idx::new(0), Idx::new(3)]).unwrap();
+        let params = SearchParametersIvf::new_with(Some(&sel), 1, 0).unwrap();
+        let my_query = [100.; D as usize];
+        let result = ConcurrentIndex::search_with_params(&index, &my_query, 2, &params).unwrap();
+        assert_eq!(result.labels, vec![Idx::new(3), Idx::new(0)]);
+        assert!(result.distances.iter().all(|x| *x > 0.));
+    }
+
     #[test]
     fn ivf_sq_index_own_search() {
         let quantizer = flat::FlatIndex::new_l2(D).unwrap();
@@ -514,4 +546,21 @@ mod tests {
         let index_impl = index.upcast();
         assert_eq!(index_impl.d(), D);
     }
+
+    #[test]
+    fn sq_index_codec() {
+        let index =
+            ScalarQuantizerIndexImpl::new(D, QuantizerType::QT_fp16, MetricType::L2).unwrap();
+        assert_eq!(index.code_size().unwrap(), 2 * D as usize);
+
+        let some_data = &[
+            7.5_f32, -7.5, 7.5, -7.5, 7.5, 7.5, 7.5, 7.5, -1., 1., 1., 1., 1., 1., 1., -1.,
+        ];
+        let codes = index.encode(some_data).unwrap();
+        assert_eq!(codes.len(), 4 * D as usize);
+        // fp16 is exact for these values
+        let decoded = index.decode(&codes).unwrap();
+        assert_eq!(&decoded[..], &some_data[..]);
+        assert!(index.decode(&codes[1..]).is_err());
+    }
 }
@@ -0,0 +1,315 @@
+//! Module for the index shards wrapper.
+//!
+//! A [`ShardedIndex`] splits a collection of vectors across several
+//! sub-indexes (the shards) of the same dimensionality. Searches are
+//! dispatched to every shard, optionally in parallel, and the partial results
+//! are merged into a single result set. Shards can be added or removed at any
+//! time, which makes it possible to roll partitions of the data in and out of
+//! the collection.
+//!
+//! When created with successive IDs, the IDs of each shard are translated by
+//! the total number of vectors in the shards preceding it. Otherwise, the IDs
+//! are reported as stored by each shard, so the shards should be built with
+//! disjoint IDs (for example, through [`IdMap`]).
+//!
+//! [`ShardedIndex`]: struct.ShardedIndex.html
+//! [`IdMap`]: ../id_map/struct.IdMap.html
+//!
+//! # Examples
+//!
+//! ```
+//! use faiss::{Idx, Index, FlatIndex};
+//! use faiss::index::shards::ShardedIndex;
+//! # fn run() -> Result<(), Box<dyn std::error::Error>> {
+//! let mut shard_1 = FlatIndex::new_l2(2)?;
+//! shard_1.add(&[0., 0., 1., 1.])?;
+//! let mut shard_2 = FlatIndex::new_l2(2)?;
+//! shard_2.add(&[2., 2.])?;
+//!
+//! let mut index = ShardedIndex::new_with_options(2, true, true)?;
+//! index.add_shard(shard_1)?;
+//! index.add_shard(shard_2)?;
+//! assert_eq!(index.ntotal(), 3);
+//!
+//! let result = index.search(&[2., 2.], 1)?;
+//! assert_eq!(result.labels, vec![Idx::new(2)]);
+//! # Ok(())
+//! # }
+//! # run().unwrap();
+//! ```
+
+use crate::error::{Error, Result};
+use crate::faiss_try;
+use crate::index::{
+    AssignSearchResult, ConcurrentIndex, CpuIndex, FromInnerPtr, Idx, Index, IndexRef, NativeIndex,
+    RangeSearchResult, SearchResult,
+};
+use crate::selector::IdSelector;
+use faiss_sys::*;
+
+use std::marker::PhantomData;
+use std::mem;
+use std::os::raw::c_int;
+use std::ptr;
+
+/// Wrapper for distributing a collection of vectors
+/// across several sub-indexes of type `I`.
+///
+/// See the [module level documentation] for more information.
+///
+/// [module level documentation]: ./index.html
+#[derive(Debug)]
+pub struct ShardedIndex<I> {
+    inner: *mut FaissIndexShards,
+    num_shards: usize,
+    phantom: PhantomData<I>,
+}
+
+unsafe impl<I: Send> Send for ShardedIndex<I> {}
+unsafe impl<I: Sync> Sync for ShardedIndex<I> {}
+impl<I: CpuIndex> CpuIndex for ShardedIndex<I> {}
+
+impl<I> NativeIndex for ShardedIndex<I> {
+    fn inner_ptr(&self) -> *mut FaissIndex {
+        self.inner
+    }
+}
+
+impl<I> Drop for ShardedIndex<I> {
+    fn drop(&mut self) {
+        unsafe {
+            faiss_IndexShards_free(self.inner);
+        }
+    }
+}
+
+impl<I> ShardedIndex<I>
+where
+    I: NativeIndex,
+{
+    /// Create a new, empty collection of shards of dimensionality `d`.
+    ///
+    /// The shards are searched sequentially,
+    /// and the IDs of each shard are successive.
+    pub fn new(d: u32) -> Result<Self> {
+        unsafe {
+            let mut inner = ptr::null_mut();
+            faiss_try(faiss_IndexShards_new(&mut inner, d as idx_t))?;
+            Ok(ShardedIndex::from_new_inner(inner))
+        }
+    }
+
+    /// Create a new, empty collection of shards of dimensionality `d`.
+    ///
+    /// If `threaded` is true, each shard is searched in a separate thread.
+    /// If `successive_ids` is true, the IDs of each shard are translated by
+    /// the number of vectors in the shards before it.
+    pub fn new_with_options(d: u32, threaded: bool, successive_ids: bool) -> Result<Self> {
+        unsafe {
+            let mut inner = ptr::null_mut();
+            faiss_try(faiss_IndexShards_new_with_options(
+                &mut inner,
+                d as idx_t,
+                c_int::from(threaded),
+                c_int::from(successive_ids),
+            ))?;
+            Ok(ShardedIndex::from_new_inner(inner))
+        }
+    }
+
+    unsafe fn from_new_inner(inner: *mut FaissIndexShards) -> Self {
+        // let the shards container take ownership of its shards
+        faiss_IndexShards_set_own_fields(inner, 1);
+        ShardedIndex {
+            inner,
+            num_shards: 0,
+            phantom: PhantomData,
+        }
+    }
+
+    /// Add a shard to the collection, which takes ownership of it.
+    ///
+    /// The shard must have the same dimensionality as the collection.
+    pub fn add_shard(&mut self, shard: I) -> Result<()> {
+        unsafe {
+            faiss_try(faiss_IndexShards_add_shard(self.inner, shard.inner_ptr()))?;
+        }
+        mem::forget(shard);
+        self.num_shards += 1;
+        Ok(())
+    }
+
+    /// Remove the shard at position `i` from the collection,
+    /// giving back ownership of it.
+    ///
+    /// The shards after it are shifted to the left.
+    pub fn remove_shard(&mut self, i: usize) -> Result<I>
+    where
+        I: FromInnerPtr,
+    {
+        let shard_inner = self.shard_inner_ptr(i).ok_or(Error::BadSubIndex)?;
+        unsafe {
+            // make the collection disown the shard while it is removed
+            faiss_IndexShards_set_own_fields(self.inner, 0);
+            let r = faiss_try(faiss_IndexShards_remove_shard(self.inner, shard_inner));
+            faiss_IndexShards_set_own_fields(self.inner, 1);
+            r?;
+            self.num_shards -= 1;
+            Ok(I::from_inner_ptr(shard_inner))
+        }
+    }
+
+    /// Obtain the number of shards in the collection.
+    pub fn num_shards(&self) -> usize {
+        self.num_shards
+    }
+
+    /// Obtain the raw pointer to the shard at position `i`,
+    /// or `None` if there is no such shard.
+    ///
+    /// # Safety
+    ///
+    /// While this method is safe, note that the returned index pointer is
+    /// already owned by this collection. Therefore, it is undefined behavior
+    /// to create a high-level index value from this pointer. See
+    /// [`shard`] and [`remove_shard`] for safe alternatives.
+    ///
+    /// [`shard`]: #method.shard
+    /// [`remove_shard`]: #method.remove_shard
+    pub fn shard_inner_ptr(&self, i: usize) -> Option<*mut FaissIndex> {
+        if i >= self.num_shards {
+            return None;
+        }
+        unsafe { Some(faiss_IndexShards_at(self.inner, i as c_int)) }
+    }
+
+    /// Obtain a view of the shard at position `i`,
+    /// or `None` if there is no such shard.
+    pub fn shard(&self, i: usize) -> Option<IndexRef<'_, I>> {
+        self.shard_inner_ptr(i)
+            .map(|inner| unsafe { IndexRef::from_inner_ptr(inner) })
+    }
+
+    /// Whether the IDs of each shard are translated by
+    /// the number of vectors in the shards before it.
+    pub fn successive_ids(&self) -> bool {
+        unsafe { faiss_IndexShards_successive_ids(self.inner) != 0 }
+    }
+
+    /// Set whether the IDs of each shard are translated by
+    /// the number of vectors in the shards before it.
+    pub fn set_successive_ids(&mut self, value: bool) {
+        unsafe {
+            faiss_IndexShards_set_successive_ids(self.inner, c_int::from(value));
+        }
+    }
+}
+
+impl_index!(ShardedIndex<I>, I);
+impl_concurrent_index!(ShardedIndex<I>, I: ConcurrentIndex);
+
+#[cfg(test)]
+mod tests {
+    use super::ShardedIndex;
+    use crate::index::{flat::FlatIndexImpl, ConcurrentIndex, Idx, Index};
+
+    const D: u32 = 8;
+
+    fn make_shards() -> (FlatIndexImpl, FlatIndexImpl) {
+        let mut shard_1 = FlatIndexImpl::new_l2(D).unwrap();
+        shard_1
+            .add(&[
+                7.5_f32, -7.5, 7.5, -7.5, 7.5, 7.5, 7.5, 7.5, -1., 1., 1., 1., 1., 1., 1., -1., 0.,
+                0., 0., 1., 1., 0., 0., -1.,
+            ])
+            .unwrap();
+        let mut shard_2 = FlatIndexImpl::new_l2(D).unwrap();
+        shard_2
+            .add(&[
+                100., 100., 100., 100., -100., 100., 100., 100., 120., 100., 100., 105., -100.,
+                100., 100., 105.,
+            ])
+            .unwrap();
+        (shard_1, shard_2)
+    }
+
+    #[test]
+    fn sharded_index_search() {
+        let (shard_1, shard_2) = make_shards();
+        let mut index = ShardedIndex::new(D).unwrap();
+        index.add_shard(shard_1).unwrap();
+        index.add_shard(shard_2).unwrap();
+        assert_eq!(index.num_shards(), 2);
+        assert_eq!(index.ntotal(), 5);
+        assert!(index.successive_ids());
+
+        let my_query = [0.; D as usize];
+        let result = index.search(&my_query, 5).unwrap();
+        assert_eq!(
+            result.labels,
+            vec![2, 1, 0, 3, 4]
+                .into_iter()
+                .map(Idx::new)
+                .collect::<Vec<_>>()
+        );
+
+        let my_query = [100.; D as usize];
+        let result = index.search(&my_query, 5).unwrap();
+        assert_eq!(
+            result.labels,
+            vec![3, 4, 0, 1, 2]
+                .into_iter()
+                .map(Idx::new)
+                .collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn sharded_index_threaded_concurrent_search() {
+        let (shard_1, shard_2) = make_shards();
+        let mut index = ShardedIndex::new_with_options(D, true, true).unwrap();
+        index.add_shard(shard_1).unwrap();
+        index.add_shard(shard_2).unwrap();
+
+        let my_query = [0.; D as usize];
+        let result = ConcurrentIndex::search(&index, &my_query, 3).unwrap();
+        assert_eq!(
+            result.labels,
+            vec![2, 1, 0].into_iter().map(Idx::new).collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn sharded_index_remove_shard() {
+        let (shard_1, shard_2) = make_shards();
+        let mut index = ShardedIndex::new(D).unwrap();
+        index.add_shard(shard_1).unwrap();
+        index.add_shard(shard_2).unwrap();
+
+        let shard: FlatIndexImpl = index.remove_shard(0).unwrap();
+        assert_eq!(shard.ntotal(), 3);
+        assert_eq!(index.num_shards(), 1);
+        assert_eq!(index.ntotal(), 2);
+        assert!(index.shard_inner_ptr(1).is_none());
+        assert!(index.shard(1).is_none());
+
+        let shard = index.shard(0).unwrap();
+        assert_eq!(shard.ntotal(), 2);
+        assert_eq!(shard.d(), D);
+        let result = shard.search(&[100.; D as usize], 1).unwrap();
+        assert_eq!(result.labels, vec![Idx::new(0)]);
+        assert!(index.remove_shard(1).is_err());
+
+        let my_query = [100.; D as usize];
+        let result = index.search(&my_query, 2).unwrap();
+        assert_eq!(result.labels, vec![Idx::new(0), Idx::new(1)]);
+    }
+
+    #[test]
+    fn sharded_index_bad_dimension() {
+        let mut index = ShardedIndex::new(D).unwrap();
+        let shard = FlatIndexImpl::new_l2(D + 1).unwrap();
+        assert!(index.add_shard(shard).is_err());
+        assert_eq!(index.num_shards(), 0);
+    }
+}
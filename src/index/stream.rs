@@ -0,0 +1,344 @@
+//! Bridging of Rust I/O streams to native `FILE` streams.
+//!
+//! The native index I/O functions work on C `FILE` streams, so Rust readers
+//! and writers are exposed through custom streams (`fopencookie` on Linux,
+//! `funopen` on macOS and the BSDs) which forward every read or write to the
+//! Rust stream. Errors and panics coming from the Rust stream are recorded,
+//! so that they can be reported once the native operation is over.
+//!
+//! Reading streams are unbuffered, so that the native operation takes no more
+//! bytes from the reader than it actually reads.
+#![cfg_attr(
+    not(any(
+        target_os = "linux",
+        target_vendor = "apple",
+        target_os = "freebsd",
+        target_os = "dragonfly",
+        target_os = "netbsd",
+        target_os = "openbsd"
+    )),
+    allow(dead_code)
+)]
+
+use crate::error::Result;
+use faiss_sys::FILE;
+use std::any::Any;
+use std::io::{self, Read, Write};
+use std::panic::{self, AssertUnwindSafe};
+use std::ptr;
+
+/// The state behind a native stream.
+struct Cookie<'a, S> {
+    stream: &'a mut S,
+    error: Option<io::Error>,
+    panic: Option<Box<dyn Any + Send>>,
+}
+
+impl<'a, S> Cookie<'a, S> {
+    fn new(stream: &'a mut S) -> Self {
+        Cookie {
+            stream,
+            error: None,
+            panic: None,
+        }
+    }
+
+    /// Run an operation on the Rust stream,
+    /// recording its error or panic if it fails.
+    fn run<F>(&mut self, op: F) -> Option<usize>
+    where
+        F: FnOnce(&mut S) -> io::Result<usize>,
+    {
+        if self.error.is_some() || self.panic.is_some() {
+            return None;
+        }
+        let stream = &mut *self.stream;
+        match panic::catch_unwind(AssertUnwindSafe(|| op(stream))) {
+            Ok(Ok(n)) => Some(n),
+            Ok(Err(e)) => {
+                self.error = Some(e);
+                None
+            }
+            Err(p) => {
+                self.panic = Some(p);
+                None
+            }
+        }
+    }
+
+    /// Resolve the outcome of a native operation,
+    /// giving priority to the failures of the Rust stream.
+    fn finish<T>(self, r: Result<T>) -> Result<T> {
+        if let Some(p) = self.panic {
+            panic::resume_unwind(p);
+        }
+        if let Some(e) = self.error {
+            return Err(e.into());
+        }
+        r
+    }
+}
+
+/// Run a native reading operation on a stream backed by the given reader.
+pub(crate) fn with_reader<R, T, F>(reader: &mut R, op: F) -> Result<T>
+where
+    R: Read,
+    F: FnOnce(*mut FILE) -> Result<T>,
+{
+    let mut cookie = Cookie::new(reader);
+    unsafe {
+        let f = sys::open_reader(&mut cookie)?;
+        // stdio would otherwise read ahead past the end of the data,
+        // taking bytes which belong to whatever follows in the reader
+        if libc::setvbuf(f, ptr::null_mut(), libc::_IONBF, 0) != 0 {
+            let e = io::Error::last_os_error();
+            libc::fclose(f);
+            return Err(e.into());
+        }
+        let r = op(f as *mut FILE);
+        libc::fclose(f);
+        cookie.finish(r)
+    }
+}
+
+/// Run a native writing operation on a stream backed by the given writer.
+///
+/// The writer is flushed at the end.
+pub(crate) fn with_writer<W, T, F>(writer: &mut W, op: F) -> Result<T>
+where
+    W: Write,
+    F: FnOnce(*mut FILE) -> Result<T>,
+{
+    let mut cookie = Cookie::new(&mut *writer);
+    let out = unsafe {
+        let f = sys::open_writer(&mut cookie)?;
+        let r = op(f as *mut FILE);
+        // closing the stream writes out any buffered data
+        let closed = libc::fclose(f);
+        let r = cookie.finish(r)?;
+        if closed != 0 {
+            return Err(io::Error::last_os_error().into());
+        }
+        r
+    };
+    writer.flush()?;
+    Ok(out)
+}
+
+fn read_retry<R: Read>(reader: &mut R, buf: &mut [u8]) -> io::Result<usize> {
+    loop {
+        match reader.read(buf) {
+            Err(ref e) if e.kind() == io::ErrorKind::Interrupted => {}
+            r => return r,
+        }
+    }
+}
+
+fn write_retry<W: Write>(writer: &mut W, buf: &[u8]) -> io::Result<usize> {
+    loop {
+        match writer.write(buf) {
+            Ok(0) if !buf.is_empty() => return Err(io::ErrorKind::WriteZero.into()),
+            Err(ref e) if e.kind() == io::ErrorKind::Interrupted => {}
+            r => return r,
+        }
+    }
+}
+
+#[cfg(target_os = "linux")]
+mod sys {
+    use super::{read_retry, write_retry, Cookie};
+    use libc::{c_char, c_int, c_void, off64_t, size_t, ssize_t, FILE};
+    use std::io::{self, Read, Write};
+    use std::slice;
+
+    #[repr(C)]
+    struct CookieIoFunctions {
+        read: Option<unsafe extern "C" fn(*mut c_void, *mut c_char, size_t) -> ssize_t>,
+        write: Option<unsafe extern "C" fn(*mut c_void, *const c_char, size_t) -> ssize_t>,
+        seek: Option<unsafe extern "C" fn(*mut c_void, *mut off64_t, c_int) -> c_int>,
+        close: Option<unsafe extern "C" fn(*mut c_void) -> c_int>,
+    }
+
+    extern "C" {
+        fn fopencookie(
+            cookie: *mut c_void,
+            mode: *const c_char,
+            io_funcs: CookieIoFunctions,
+        ) -> *mut FILE;
+    }
+
+    unsafe extern "C" fn read_fn<R: Read>(
+        cookie: *mut c_void,
+        buf: *mut c_char,
+        size: size_t,
+    ) -> ssize_t {
+        let cookie = &mut *(cookie as *mut Cookie<R>);
+        let buf = slice::from_raw_parts_mut(buf as *mut u8, size);
+        cookie
+            .run(|reader| read_retry(reader, buf))
+            .map_or(-1, |n| n as ssize_t)
+    }
+
+    unsafe extern "C" fn write_fn<W: Write>(
+        cookie: *mut c_void,
+        buf: *const c_char,
+        size: size_t,
+    ) -> ssize_t {
+        let cookie = &mut *(cookie as *mut Cookie<W>);
+        let buf = slice::from_raw_parts(buf as *const u8, size);
+        // a custom stream signals a write error by writing nothing
+        cookie
+            .run(|writer| write_retry(writer, buf))
+            .map_or(0, |n| n as ssize_t)
+    }
+
+    pub(super) unsafe fn open_reader<R: Read>(cookie: &mut Cookie<R>) -> io::Result<*mut FILE> {
+        let funcs = CookieIoFunctions {
+            read: Some(read_fn::<R>),
+            write: None,
+            seek: None,
+            close: None,
+        };
+        let f = fopencookie(
+            cookie as *mut Cookie<R> as *mut c_void,
+            b"rb\0".as_ptr() as *const c_char,
+            funcs,
+        );
+        if f.is_null() {
+            Err(io::Error::last_os_error())
+        } else {
+            Ok(f)
+        }
+    }
+
+    pub(super) unsafe fn open_writer<W: Write>(cookie: &mut Cookie<W>) -> io::Result<*mut FILE> {
+        let funcs = CookieIoFunctions {
+            read: None,
+            write: Some(write_fn::<W>),
+            seek: None,
+            close: None,
+        };
+        let f = fopencookie(
+            cookie as *mut Cookie<W> as *mut c_void,
+            b"wb\0".as_ptr() as *const c_char,
+            funcs,
+        );
+        if f.is_null() {
+            Err(io::Error::last_os_error())
+        } else {
+            Ok(f)
+        }
+    }
+}
+
+#[cfg(any(
+    target_vendor = "apple",
+    target_os = "freebsd",
+    target_os = "dragonfly",
+    target_os = "netbsd",
+    target_os = "openbsd"
+))]
+mod sys {
+    use super::{read_retry, write_retry, Cookie};
+    use libc::{c_char, c_int, c_void, off_t, FILE};
+    use std::io::{self, Read, Write};
+    use std::slice;
+
+    type ReadFn = unsafe extern "C" fn(*mut c_void, *mut c_char, c_int) -> c_int;
+    type WriteFn = unsafe extern "C" fn(*mut c_void, *const c_char, c_int) -> c_int;
+    type SeekFn = unsafe extern "C" fn(*mut c_void, off_t, c_int) -> off_t;
+    type CloseFn = unsafe extern "C" fn(*mut c_void) -> c_int;
+
+    extern "C" {
+        fn funopen(
+            cookie: *const c_void,
+            readfn: Option<ReadFn>,
+            writefn: Option<WriteFn>,
+            seekfn: Option<SeekFn>,
+            closefn: Option<CloseFn>,
+        ) -> *mut FILE;
+    }
+
+    unsafe extern "C" fn read_fn<R: Read>(
+        cookie: *mut c_void,
+        buf: *mut c_char,
+        size: c_int,
+    ) -> c_int {
+        let cookie = &mut *(cookie as *mut Cookie<R>);
+        let buf = slice::from_raw_parts_mut(buf as *mut u8, size as usize);
+        cookie
+            .run(|reader| read_retry(reader, buf))
+            .map_or(-1, |n| n as c_int)
+    }
+
+    unsafe extern "C" fn write_fn<W: Write>(
+        cookie: *mut c_void,
+        buf: *const c_char,
+        size: c_int,
+    ) -> c_int {
+        let cookie = &mut *(cookie as *mut Cookie<W>);
+        let buf = slice::from_raw_parts(buf as *const u8, size as usize);
+        cookie
+            .run(|writer| write_retry(writer, buf))
+            .map_or(-1, |n| n as c_int)
+    }
+
+    pub(super) unsafe fn open_reader<R: Read>(cookie: &mut Cookie<R>) -> io::Result<*mut FILE> {
+        let f = funopen(
+            cookie as *mut Cookie<R> as *const c_void,
+            Some(read_fn::<R>),
+            None,
+            None,
+            None,
+        );
+        if f.is_null() {
+            Err(io::Error::last_os_error())
+        } else {
+            Ok(f)
+        }
+    }
+
+    pub(super) unsafe fn open_writer<W: Write>(cookie: &mut Cookie<W>) -> io::Result<*mut FILE> {
+        let f = funopen(
+            cookie as *mut Cookie<W> as *const c_void,
+            None,
+            Some(write_fn::<W>),
+            None,
+            None,
+        );
+        if f.is_null() {
+            Err(io::Error::last_os_error())
+        } else {
+            Ok(f)
+        }
+    }
+}
+
+#[cfg(not(any(
+    target_os = "linux",
+    target_vendor = "apple",
+    target_os = "freebsd",
+    target_os = "dragonfly",
+    target_os = "netbsd",
+    target_os = "openbsd"
+)))]
+mod sys {
+    use super::Cookie;
+    use libc::FILE;
+    use std::io;
+
+    fn unsupported() -> io::Error {
+        io::Error::new(
+            io::ErrorKind::Other,
+            "streaming index I/O is not supported on this platform",
+        )
+    }
+
+    pub(super) unsafe fn open_reader<R>(_cookie: &mut Cookie<R>) -> io::Result<*mut FILE> {
+        Err(unsupported())
+    }
+
+    pub(super) unsafe fn open_writer<W>(_cookie: &mut Cookie<W>) -> io::Result<*mut FILE> {
+        Err(unsupported())
+    }
+}
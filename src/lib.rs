@@ -88,22 +88,28 @@
 mod macros;
 
 pub mod cluster;
+pub mod config;
+pub mod distances;
 pub mod error;
 pub mod index;
 pub mod metric;
+pub mod search_params;
 pub mod selector;
 pub mod utils;
 pub mod vector_transform;
+pub mod version;
 
 #[cfg(feature = "gpu")]
 pub mod gpu;
 
+pub use index::binary::{binary_index_factory, BinaryIndex};
 pub use index::flat::FlatIndex;
-pub use index::id_map::IdMap;
+pub use index::id_map::{IdMap, IdMap2};
 pub use index::io::{read_index, write_index};
 pub use index::lsh::LshIndex;
 pub use index::{index_factory, ConcurrentIndex, Idx, Index};
 pub use metric::MetricType;
+pub use version::version;
 
 #[cfg(feature = "gpu")]
 pub use gpu::{GpuResources, StandardGpuResources};
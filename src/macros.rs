@@ -130,6 +130,29 @@ macro_rules! impl_index {
                 }
             }
 
+            fn search_with_params(
+                &mut self,
+                query: &[f32],
+                k: usize,
+                params: &crate::search_params::SearchParameters,
+            ) -> Result<crate::index::SearchResult> {
+                unsafe {
+                    let nq = query.len() / self.d() as usize;
+                    let mut distances = vec![0_f32; k * nq];
+                    let mut labels = vec![Idx::none(); k * nq];
+                    faiss_try(faiss_Index_search_with_params(
+                        self.inner_ptr(),
+                        nq as idx_t,
+                        query.as_ptr(),
+                        k as idx_t,
+                        params.inner_ptr(),
+                        distances.as_mut_ptr(),
+                        labels.as_mut_ptr() as *mut _,
+                    ))?;
+                    Ok(crate::index::SearchResult { distances, labels })
+                }
+            }
+
             fn range_search(
                 &mut self,
                 query: &[f32],
@@ -194,6 +217,49 @@ macro_rules! impl_index {
                 }
             }
 
+            fn compute_residual(&self, x: &[f32], key: Idx) -> Result<Vec<f32>> {
+                unsafe {
+                    let d = self.d() as usize;
+                    if d != x.len() {
+                        return Err(crate::error::Error::BadDimension);
+                    }
+
+                    let mut residual = vec![0_f32; d];
+                    faiss_try(faiss_Index_compute_residual(
+                        self.inner_ptr(),
+                        x.as_ptr(),
+                        residual.as_mut_ptr(),
+                        key.0,
+                    ))?;
+
+                    Ok(residual)
+                }
+            }
+
+            fn compute_residual_n(
+                &self,
+                xs: &[f32],
+                keys: &[Idx]
+            ) -> Result<Vec<f32>> {
+                unsafe {
+                    let d = self.d() as usize;
+                    if keys.len() * d != xs.len() {
+                        return Err(crate::error::Error::BadDimension);
+                    }
+
+                    let mut residuals = vec![0_f32; xs.len()];
+                    faiss_try(faiss_Index_compute_residual_n(
+                        self.inner_ptr(),
+                        keys.len() as idx_t,
+                        xs.as_ptr(),
+                        residuals.as_mut_ptr(),
+                        keys.as_ptr() as *const _,
+                    ))?;
+
+                    Ok(residuals)
+                }
+            }
+
             fn reset(&mut self) -> Result<()> {
                 unsafe {
                     faiss_try(faiss_Index_reset(self.inner_ptr()))?;
@@ -267,6 +333,29 @@ macro_rules! impl_concurrent_index {
                 }
             }
 
+            fn search_with_params(
+                &self,
+                query: &[f32],
+                k: usize,
+                params: &crate::search_params::SearchParameters,
+            ) -> Result<SearchResult> {
+                unsafe {
+                    let nq = query.len() / self.d() as usize;
+                    let mut distances = vec![0_f32; k * nq];
+                    let mut labels = vec![Idx::none(); k * nq];
+                    faiss_try(faiss_Index_search_with_params(
+                        self.inner_ptr(),
+                        nq as idx_t,
+                        query.as_ptr(),
+                        k as idx_t,
+                        params.inner_ptr(),
+                        distances.as_mut_ptr(),
+                        labels.as_mut_ptr() as *mut _,
+                    ))?;
+                    Ok(SearchResult { distances, labels })
+                }
+            }
+
             fn range_search(&self, query: &[f32], radius: f32) -> Result<RangeSearchResult> {
                 unsafe {
                     let nq = (query.len() / self.d() as usize) as idx_t;
@@ -285,3 +374,201 @@ macro_rules! impl_concurrent_index {
         }
     };
 }
+
+/// A macro which provides a native standalone codec implementation to the given type.
+macro_rules! impl_native_codec {
+    ($t:ty) => {
+        impl crate::index::codec::Codec for $t
+        where
+            Self: crate::index::Index + crate::index::NativeIndex,
+        {
+            fn code_size(&self) -> Result<usize> {
+                unsafe {
+                    let mut size = 0;
+                    faiss_try(faiss_Index_sa_code_size(self.inner_ptr(), &mut size))?;
+                    Ok(size)
+                }
+            }
+
+            fn encode(&self, x: &[f32]) -> Result<Vec<u8>> {
+                let d = self.d() as usize;
+                if x.len() % d != 0 {
+                    return Err(crate::error::Error::BadDimension);
+                }
+                let n = x.len() / d;
+                let mut codes = vec![0_u8; n * crate::index::codec::Codec::code_size(self)?];
+                unsafe {
+                    faiss_try(faiss_Index_sa_encode(
+                        self.inner_ptr(),
+                        n as idx_t,
+                        x.as_ptr(),
+                        codes.as_mut_ptr(),
+                    ))?;
+                }
+                Ok(codes)
+            }
+
+            fn decode(&self, codes: &[u8]) -> Result<Vec<f32>> {
+                let code_size = crate::index::codec::Codec::code_size(self)?;
+                if code_size == 0 || codes.len() % code_size != 0 {
+                    return Err(crate::error::Error::BadDimension);
+                }
+                let n = codes.len() / code_size;
+                let mut x = vec![0_f32; n * self.d() as usize];
+                unsafe {
+                    faiss_try(faiss_Index_sa_decode(
+                        self.inner_ptr(),
+                        n as idx_t,
+                        codes.as_ptr(),
+                        x.as_mut_ptr(),
+                    ))?;
+                }
+                Ok(x)
+            }
+        }
+    };
+}
+
+/// A macro which provides a native IVF index implementation to the given type,
+/// with the given coarse quantizer type.
+macro_rules! impl_native_ivf_index {
+    ($t:ty, $q:ty) => { impl_native_ivf_index!($t, $q,); };
+    ($t:ty, $q:ty, $($g:ident $(: $bound:tt)?),*) => {
+        impl<$($g),*> crate::index::ivf::IvfIndex for $t
+        where
+            Self: crate::index::Index + crate::index::NativeIndex,
+            $($($g: $bound,)?)*
+        {
+            type Quantizer = $q;
+
+            fn nlist(&self) -> u32 {
+                unsafe { faiss_IndexIVF_nlist(self.inner_ptr()) as u32 }
+            }
+
+            fn nprobe(&self) -> u32 {
+                unsafe { faiss_IndexIVF_nprobe(self.inner_ptr()) as u32 }
+            }
+
+            fn set_nprobe(&mut self, value: u32) {
+                unsafe {
+                    faiss_IndexIVF_set_nprobe(self.inner_ptr(), value as usize);
+                }
+            }
+
+            fn quantizer(&self) -> crate::index::IndexRef<'_, $q> {
+                unsafe {
+                    let quantizer = faiss_IndexIVF_quantizer(self.inner_ptr());
+                    crate::index::IndexRef::from_inner_ptr(quantizer)
+                }
+            }
+
+            fn merge_from<I>(&mut self, other: &mut I, add_id: u64) -> Result<()>
+            where
+                I: crate::index::ivf::IvfIndex,
+            {
+                unsafe {
+                    faiss_try(faiss_IndexIVF_merge_from(
+                        self.inner_ptr(),
+                        other.inner_ptr(),
+                        add_id as idx_t,
+                    ))?;
+                    Ok(())
+                }
+            }
+
+            fn copy_subset_to<I>(
+                &self,
+                other: &mut I,
+                subset: crate::index::ivf::SubsetType,
+            ) -> Result<()>
+            where
+                I: crate::index::ivf::IvfIndex,
+            {
+                let (subset_type, a1, a2) = subset.to_raw();
+                unsafe {
+                    faiss_try(faiss_IndexIVF_copy_subset_to(
+                        self.inner_ptr(),
+                        other.inner_ptr(),
+                        subset_type,
+                        a1,
+                        a2,
+                    ))?;
+                    Ok(())
+                }
+            }
+
+            fn search_preassigned(
+                &self,
+                query: &[f32],
+                k: usize,
+                assign: &[Idx],
+                centroid_dis: &[f32],
+            ) -> Result<SearchResult> {
+                let d = self.d() as usize;
+                if query.len() % d != 0 {
+                    return Err(crate::error::Error::BadDimension);
+                }
+                let nq = query.len() / d;
+                let nprobe = crate::index::ivf::IvfIndex::nprobe(self) as usize;
+                if assign.len() != nq * nprobe || centroid_dis.len() != nq * nprobe {
+                    return Err(crate::error::Error::BadDimension);
+                }
+                let mut distances = vec![0_f32; k * nq];
+                let mut labels = vec![Idx::none(); k * nq];
+                unsafe {
+                    faiss_try(faiss_IndexIVF_search_preassigned(
+                        self.inner_ptr(),
+                        nq as idx_t,
+                        query.as_ptr(),
+                        k as idx_t,
+                        assign.as_ptr() as *const _,
+                        centroid_dis.as_ptr(),
+                        distances.as_mut_ptr(),
+                        labels.as_mut_ptr() as *mut _,
+                        0,
+                    ))?;
+                }
+                Ok(SearchResult { distances, labels })
+            }
+
+            fn list_size(&self, list_no: usize) -> usize {
+                assert!(
+                    list_no < crate::index::ivf::IvfIndex::nlist(self) as usize,
+                    "inverted list number out of bounds"
+                );
+                unsafe { faiss_IndexIVF_get_list_size(self.inner_ptr(), list_no) }
+            }
+
+            fn list_ids(&self, list_no: usize) -> Vec<Idx> {
+                let size = crate::index::ivf::IvfIndex::list_size(self, list_no);
+                let mut ids = vec![Idx::none(); size];
+                unsafe {
+                    faiss_IndexIVF_invlists_get_ids(
+                        self.inner_ptr(),
+                        list_no,
+                        ids.as_mut_ptr() as *mut _,
+                    );
+                }
+                ids
+            }
+
+            fn make_direct_map(&mut self, enabled: bool) -> Result<()> {
+                unsafe {
+                    faiss_try(faiss_IndexIVF_make_direct_map(
+                        self.inner_ptr(),
+                        std::os::raw::c_int::from(enabled),
+                    ))?;
+                    Ok(())
+                }
+            }
+
+            fn imbalance_factor(&self) -> f64 {
+                unsafe { faiss_IndexIVF_imbalance_factor(self.inner_ptr()) }
+            }
+
+            fn print_stats(&self) {
+                unsafe { faiss_IndexIVF_print_stats(self.inner_ptr()) }
+            }
+        }
+    };
+}
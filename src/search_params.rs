@@ -0,0 +1,208 @@
+//! Search-time parameters
+//!
+//! A [`SearchParameters`] value can be passed to
+//! [`Index::search_with_params`] in order to customize a single search
+//! operation, such as restricting the results to the vectors accepted by an
+//! [`IdSelector`]. IVF indexes take [`SearchParametersIvf`] instead, which
+//! also define the number of probes at query time, so that it does not have
+//! to be set on the index itself.
+//!
+//! [`SearchParameters`]: struct.SearchParameters.html
+//! [`SearchParametersIvf`]: struct.SearchParametersIvf.html
+//! [`Index::search_with_params`]: ../index/trait.Index.html#tymethod.search_with_params
+//! [`IdSelector`]: ../selector/struct.IdSelector.html
+//!
+//! # Examples
+//!
+//! ```
+//! use faiss::{Idx, Index, FlatIndex};
+//! use faiss::search_params::SearchParameters;
+//! use faiss::selector::IdSelector;
+//! # fn run() -> Result<(), Box<dyn std::error::Error>> {
+//! let mut index = FlatIndex::new_l2(2)?;
+//! index.add(&[0., 0., 1., 1., 2., 2.])?;
+//!
+//! let sel = IdSelector::batch(&[Idx::new(1), Idx::new(2)])?;
+//! let params = SearchParameters::with_selector(&sel)?;
+//! let result = index.search_with_params(&[0., 0.], 1, &params)?;
+//! assert_eq!(result.labels, vec![Idx::new(1)]);
+//! # Ok(())
+//! # }
+//! # run().unwrap();
+//! ```
+
+use crate::error::Result;
+use crate::faiss_try;
+use crate::selector::IdSelector;
+use faiss_sys::*;
+use std::marker::PhantomData;
+use std::ops::Deref;
+use std::ptr;
+
+/// Generic search-time parameters.
+///
+/// The `'sel` lifetime ensures that the ID selector, if any, outlives the
+/// parameters which refer to it.
+#[derive(Debug)]
+pub struct SearchParameters<'sel> {
+    inner: *mut FaissSearchParameters,
+    selector: PhantomData<&'sel IdSelector<'sel>>,
+}
+
+unsafe impl Send for SearchParameters<'_> {}
+unsafe impl Sync for SearchParameters<'_> {}
+
+impl Drop for SearchParameters<'_> {
+    fn drop(&mut self) {
+        unsafe {
+            faiss_SearchParameters_free(self.inner);
+        }
+    }
+}
+
+impl SearchParameters<'static> {
+    /// Create new search parameters without an ID selector.
+    pub fn new() -> Result<Self> {
+        SearchParameters::new_helper(ptr::null_mut())
+    }
+}
+
+impl<'sel> SearchParameters<'sel> {
+    /// Create new search parameters which only admit the vectors
+    /// accepted by the given ID selector.
+    pub fn with_selector(sel: &'sel IdSelector<'_>) -> Result<Self> {
+        SearchParameters::new_helper(sel.inner_ptr())
+    }
+
+    fn new_helper(sel: *mut FaissIDSelector) -> Result<Self> {
+        unsafe {
+            let mut inner = ptr::null_mut();
+            faiss_try(faiss_SearchParameters_new(&mut inner, sel))?;
+            Ok(SearchParameters {
+                inner,
+                selector: PhantomData,
+            })
+        }
+    }
+
+    /// Return the inner pointer
+    pub fn inner_ptr(&self) -> *mut FaissSearchParameters {
+        self.inner
+    }
+}
+
+/// Search-time parameters for IVF indexes.
+///
+/// These parameters dereference to [`SearchParameters`], and so can be passed
+/// to the same search methods.
+///
+/// [`SearchParameters`]: struct.SearchParameters.html
+#[derive(Debug)]
+pub struct SearchParametersIvf<'sel> {
+    base: SearchParameters<'sel>,
+}
+
+impl SearchParametersIvf<'static> {
+    /// Create new IVF search parameters with the default values
+    /// and without an ID selector.
+    pub fn new() -> Result<Self> {
+        unsafe {
+            let mut inner = ptr::null_mut();
+            faiss_try(faiss_SearchParametersIVF_new(&mut inner))?;
+            Ok(SearchParametersIvf {
+                base: SearchParameters {
+                    inner,
+                    selector: PhantomData,
+                },
+            })
+        }
+    }
+}
+
+impl<'sel> SearchParametersIvf<'sel> {
+    /// Create new IVF search parameters with the given ID selector, number of
+    /// probes at query time, and maximum number of codes to visit
+    /// (0 for no limit).
+    pub fn new_with(
+        sel: Option<&'sel IdSelector<'_>>,
+        nprobe: u32,
+        max_codes: usize,
+    ) -> Result<Self> {
+        unsafe {
+            let sel = sel.map_or(ptr::null_mut(), IdSelector::inner_ptr);
+            let mut inner = ptr::null_mut();
+            faiss_try(faiss_SearchParametersIVF_new_with(
+                &mut inner,
+                sel,
+                nprobe as usize,
+                max_codes,
+            ))?;
+            Ok(SearchParametersIvf {
+                base: SearchParameters {
+                    inner,
+                    selector: PhantomData,
+                },
+            })
+        }
+    }
+
+    /// Get number of probes at query time
+    pub fn nprobe(&self) -> u32 {
+        unsafe { faiss_SearchParametersIVF_nprobe(self.inner_ptr()) as u32 }
+    }
+
+    /// Set number of probes at query time
+    pub fn set_nprobe(&mut self, value: u32) {
+        unsafe {
+            faiss_SearchParametersIVF_set_nprobe(self.inner_ptr(), value as usize);
+        }
+    }
+
+    /// Get maximum number of codes to visit at query time (0 for no limit)
+    pub fn max_codes(&self) -> usize {
+        unsafe { faiss_SearchParametersIVF_max_codes(self.inner_ptr()) }
+    }
+
+    /// Set maximum number of codes to visit at query time (0 for no limit)
+    pub fn set_max_codes(&mut self, value: usize) {
+        unsafe {
+            faiss_SearchParametersIVF_set_max_codes(self.inner_ptr(), value);
+        }
+    }
+
+    /// Whether these parameters hold an ID selector
+    pub fn has_selector(&self) -> bool {
+        unsafe { !faiss_SearchParametersIVF_sel(self.inner_ptr()).is_null() }
+    }
+}
+
+impl<'sel> Deref for SearchParametersIvf<'sel> {
+    type Target = SearchParameters<'sel>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.base
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::SearchParametersIvf;
+    use crate::index::Idx;
+    use crate::selector::IdSelector;
+
+    #[test]
+    fn ivf_params_getters_setters() {
+        let mut params = SearchParametersIvf::new().unwrap();
+        assert!(!params.has_selector());
+        params.set_nprobe(16);
+        assert_eq!(params.nprobe(), 16);
+        params.set_max_codes(1000);
+        assert_eq!(params.max_codes(), 1000);
+
+        let sel = IdSelector::range(Idx::new(0), Idx::new(10)).unwrap();
+        let params = SearchParametersIvf::new_with(Some(&sel), 4, 0).unwrap();
+        assert!(params.has_selector());
+        assert_eq!(params.nprobe(), 4);
+        assert_eq!(params.max_codes(), 0);
+    }
+}
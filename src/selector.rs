@@ -3,15 +3,25 @@ use crate::error::Result;
 use crate::faiss_try;
 use crate::index::Idx;
 use faiss_sys::*;
+use std::marker::PhantomData;
 use std::ptr;
 
-/// Abstraction over IDSelectorRange and IDSelectorBatch
+/// Abstraction over IDSelectorRange, IDSelectorBatch and IDSelectorBitmap,
+/// as well as their boolean combinations.
+///
+/// The `'a` lifetime ties the selector to any memory which it borrows
+/// (such as the bits of a bitmap selector),
+/// and is `'static` for selectors which own all of their data.
 #[derive(Debug)]
-pub struct IdSelector {
+pub struct IdSelector<'a> {
     inner: *mut FaissIDSelector,
+    /// the selectors referred to by this one,
+    /// which need to live at least as long
+    _operands: Vec<IdSelector<'a>>,
+    bits: PhantomData<&'a [u8]>,
 }
 
-impl IdSelector {
+impl IdSelector<'static> {
     /// Create new range selector
     pub fn range(min: Idx, max: Idx) -> Result<Self> {
         let mut p_sel = ptr::null_mut();
@@ -22,9 +32,7 @@ impl IdSelector {
                 max.to_native(),
             ))?;
         };
-        Ok(IdSelector {
-            inner: p_sel as *mut _,
-        })
+        Ok(IdSelector::from_inner(p_sel as *mut _, Vec::new()))
     }
 
     /// Create new batch selector
@@ -38,9 +46,89 @@ impl IdSelector {
                 indices.as_ptr() as *const _,
             ))?;
         };
-        Ok(IdSelector {
-            inner: p_sel as *mut _,
-        })
+        Ok(IdSelector::from_inner(p_sel as *mut _, Vec::new()))
+    }
+}
+
+impl<'a> IdSelector<'a> {
+    /// Create new bitmap selector, which tests membership directly against
+    /// the given bits without copying them.
+    ///
+    /// The ID `i` is admitted if and only if bit number `i % 8` of
+    /// `bitmap[i / 8]` is set. IDs beyond the end of the bitmap are
+    /// not admitted.
+    pub fn bitmap(bitmap: &'a [u8]) -> Result<Self> {
+        let mut p_sel = ptr::null_mut();
+        unsafe {
+            faiss_try(faiss_IDSelectorBitmap_new(
+                &mut p_sel,
+                bitmap.len(),
+                bitmap.as_ptr(),
+            ))?;
+        };
+        Ok(IdSelector::from_inner(p_sel as *mut _, Vec::new()))
+    }
+
+    /// Create a selector which admits the IDs not admitted by `sel`.
+    pub fn negate(sel: IdSelector<'a>) -> Result<Self> {
+        let mut p_sel = ptr::null_mut();
+        unsafe {
+            faiss_try(faiss_IDSelectorNot_new(&mut p_sel, sel.inner_ptr()))?;
+        };
+        Ok(IdSelector::from_inner(p_sel as *mut _, vec![sel]))
+    }
+
+    /// Create a selector which admits the IDs admitted by both `lhs` and `rhs`.
+    pub fn and(lhs: IdSelector<'a>, rhs: IdSelector<'a>) -> Result<Self> {
+        let mut p_sel = ptr::null_mut();
+        unsafe {
+            faiss_try(faiss_IDSelectorAnd_new(
+                &mut p_sel,
+                lhs.inner_ptr(),
+                rhs.inner_ptr(),
+            ))?;
+        };
+        Ok(IdSelector::from_inner(p_sel as *mut _, vec![lhs, rhs]))
+    }
+
+    /// Create a selector which admits the IDs admitted by `lhs` or `rhs`.
+    pub fn or(lhs: IdSelector<'a>, rhs: IdSelector<'a>) -> Result<Self> {
+        let mut p_sel = ptr::null_mut();
+        unsafe {
+            faiss_try(faiss_IDSelectorOr_new(
+                &mut p_sel,
+                lhs.inner_ptr(),
+                rhs.inner_ptr(),
+            ))?;
+        };
+        Ok(IdSelector::from_inner(p_sel as *mut _, vec![lhs, rhs]))
+    }
+
+    /// Create a selector which admits the IDs admitted by
+    /// either `lhs` or `rhs`, but not both.
+    pub fn xor(lhs: IdSelector<'a>, rhs: IdSelector<'a>) -> Result<Self> {
+        let mut p_sel = ptr::null_mut();
+        unsafe {
+            faiss_try(faiss_IDSelectorXOr_new(
+                &mut p_sel,
+                lhs.inner_ptr(),
+                rhs.inner_ptr(),
+            ))?;
+        };
+        Ok(IdSelector::from_inner(p_sel as *mut _, vec![lhs, rhs]))
+    }
+
+    fn from_inner(inner: *mut FaissIDSelector, operands: Vec<IdSelector<'a>>) -> Self {
+        IdSelector {
+            inner,
+            _operands: operands,
+            bits: PhantomData,
+        }
+    }
+
+    /// Check whether the given ID is admitted by this selector.
+    pub fn is_member(&self, id: Idx) -> bool {
+        unsafe { faiss_IDSelector_is_member(self.inner, id.to_native()) != 0 }
     }
 
     /// Return the inner pointer
@@ -49,13 +137,87 @@ impl IdSelector {
     }
 }
 
-impl Drop for IdSelector {
+impl Drop for IdSelector<'_> {
     fn drop(&mut self) {
+        // the operands are only dropped afterwards
         unsafe {
             faiss_IDSelector_free(self.inner);
         }
     }
 }
 
-unsafe impl Send for IdSelector {}
-unsafe impl Sync for IdSelector {}
+unsafe impl Send for IdSelector<'_> {}
+unsafe impl Sync for IdSelector<'_> {}
+
+#[cfg(test)]
+mod tests {
+    use super::IdSelector;
+    use crate::index::Idx;
+
+    #[test]
+    fn range_and_batch_membership() {
+        let sel = IdSelector::range(Idx::new(2), Idx::new(5)).unwrap();
+        assert!(!sel.is_member(Idx::new(1)));
+        assert!(sel.is_member(Idx::new(2)));
+        assert!(sel.is_member(Idx::new(4)));
+        assert!(!sel.is_member(Idx::new(5)));
+
+        let sel = IdSelector::batch(&[Idx::new(3), Idx::new(7)]).unwrap();
+        assert!(sel.is_member(Idx::new(3)));
+        assert!(!sel.is_member(Idx::new(4)));
+        assert!(sel.is_member(Idx::new(7)));
+    }
+
+    #[test]
+    fn bitmap_membership() {
+        // IDs 0, 3, 8 and 15
+        let bits = [0b0000_1001_u8, 0b1000_0001];
+        let sel = IdSelector::bitmap(&bits).unwrap();
+        assert!(sel.is_member(Idx::new(0)));
+        assert!(!sel.is_member(Idx::new(1)));
+        assert!(sel.is_member(Idx::new(3)));
+        assert!(sel.is_member(Idx::new(8)));
+        assert!(!sel.is_member(Idx::new(9)));
+        assert!(sel.is_member(Idx::new(15)));
+        // out of the bitmap's range
+        assert!(!sel.is_member(Idx::new(16)));
+        assert!(!sel.is_member(Idx::new(1000)));
+    }
+
+    #[test]
+    fn boolean_combinations() {
+        // tenant range AND NOT deleted batch
+        let tenant = IdSelector::range(Idx::new(0), Idx::new(10)).unwrap();
+        let deleted = IdSelector::batch(&[Idx::new(3), Idx::new(12)]).unwrap();
+        let sel = IdSelector::and(tenant, IdSelector::negate(deleted).unwrap()).unwrap();
+        assert!(sel.is_member(Idx::new(0)));
+        assert!(!sel.is_member(Idx::new(3)));
+        assert!(sel.is_member(Idx::new(9)));
+        assert!(!sel.is_member(Idx::new(12)));
+
+        // bitmap selectors can be combined with owned ones
+        let bits = [0b0000_0101_u8];
+        let visible = IdSelector::bitmap(&bits).unwrap();
+        let sel = IdSelector::and(
+            visible,
+            IdSelector::range(Idx::new(1), Idx::new(8)).unwrap(),
+        )
+        .unwrap();
+        assert!(!sel.is_member(Idx::new(0)));
+        assert!(sel.is_member(Idx::new(2)));
+
+        let lhs = IdSelector::range(Idx::new(0), Idx::new(4)).unwrap();
+        let rhs = IdSelector::range(Idx::new(2), Idx::new(6)).unwrap();
+        let sel = IdSelector::or(lhs, rhs).unwrap();
+        assert!(sel.is_member(Idx::new(0)));
+        assert!(sel.is_member(Idx::new(5)));
+        assert!(!sel.is_member(Idx::new(6)));
+
+        let lhs = IdSelector::range(Idx::new(0), Idx::new(4)).unwrap();
+        let rhs = IdSelector::range(Idx::new(2), Idx::new(6)).unwrap();
+        let sel = IdSelector::xor(lhs, rhs).unwrap();
+        assert!(sel.is_member(Idx::new(1)));
+        assert!(!sel.is_member(Idx::new(3)));
+        assert!(sel.is_member(Idx::new(5)));
+    }
+}
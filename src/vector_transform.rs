@@ -1,9 +1,20 @@
 //! Vector transformation implementation
-
-use crate::error::Result;
+//!
+//! Trained transforms can be saved with [`write_vector_transform`] and loaded
+//! back with [`read_vector_transform`], independently of any index. The
+//! loaded transform is dynamically typed, and can be downcast to its concrete
+//! type.
+//!
+//! [`write_vector_transform`]: fn.write_vector_transform.html
+//! [`read_vector_transform`]: fn.read_vector_transform.html
+
+use crate::error::{Error, Result};
 use crate::faiss_try;
+use crate::index::io::path_to_cstring;
 use faiss_sys::*;
+use std::mem;
 use std::os::raw::c_int;
+use std::path::Path;
 use std::ptr;
 
 /// Trait for native implementations of a Faiss VectorTransform.
@@ -106,6 +117,138 @@ pub trait LinearTransform: VectorTransform {
     fn is_orthonormal(&self) -> bool;
 }
 
+/// Native implementation of a vector transform of any type.
+///
+/// Transforms read from a file are of this type. They can be downcast to
+/// their concrete type through the `into_*` methods.
+#[derive(Debug)]
+pub struct VectorTransformImpl {
+    inner: *mut FaissVectorTransform,
+}
+
+unsafe impl Send for VectorTransformImpl {}
+unsafe impl Sync for VectorTransformImpl {}
+
+impl Drop for VectorTransformImpl {
+    fn drop(&mut self) {
+        unsafe {
+            faiss_VectorTransform_free(self.inner);
+        }
+    }
+}
+
+impl NativeVectorTransform for VectorTransformImpl {
+    fn inner_ptr(&self) -> *mut FaissVectorTransform {
+        self.inner
+    }
+}
+
+impl VectorTransformImpl {
+    /// Attempt a dynamic cast of the transform with the given native cast
+    /// function, giving up ownership of the native object on success.
+    fn cast(
+        self,
+        cast_fn: unsafe extern "C" fn(*mut FaissVectorTransform) -> *mut FaissVectorTransform,
+    ) -> Result<*mut FaissVectorTransform> {
+        unsafe {
+            let new_inner = cast_fn(self.inner);
+            if new_inner.is_null() {
+                Err(Error::BadCast)
+            } else {
+                mem::forget(self);
+                Ok(new_inner)
+            }
+        }
+    }
+
+    /// Attempt a dynamic cast to a random rotation matrix.
+    pub fn into_random_rotation_matrix(self) -> Result<RandomRotationMatrixImpl> {
+        let inner = self.cast(faiss_RandomRotationMatrix_cast)?;
+        Ok(RandomRotationMatrixImpl { inner })
+    }
+
+    /// Attempt a dynamic cast to a PCA matrix.
+    pub fn into_pca_matrix(self) -> Result<PCAMatrixImpl> {
+        let inner = self.cast(faiss_PCAMatrix_cast)?;
+        Ok(PCAMatrixImpl { inner })
+    }
+
+    /// Attempt a dynamic cast to an ITQ matrix.
+    pub fn into_itq_matrix(self) -> Result<ITQMatrixImpl> {
+        let inner = self.cast(faiss_ITQMatrix_cast)?;
+        Ok(ITQMatrixImpl { inner })
+    }
+
+    /// Attempt a dynamic cast to an ITQ transform.
+    pub fn into_itq_transform(self) -> Result<ITQTransformImpl> {
+        let inner = self.cast(faiss_ITQTransform_cast)?;
+        Ok(ITQTransformImpl { inner })
+    }
+
+    /// Attempt a dynamic cast to an OPQ matrix.
+    pub fn into_opq_matrix(self) -> Result<OPQMatrixImpl> {
+        let inner = self.cast(faiss_OPQMatrix_cast)?;
+        Ok(OPQMatrixImpl { inner })
+    }
+
+    /// Attempt a dynamic cast to a dimension remapping transform.
+    pub fn into_remap_dimensions_transform(self) -> Result<RemapDimensionsTransformImpl> {
+        let inner = self.cast(faiss_RemapDimensionsTransform_cast)?;
+        Ok(RemapDimensionsTransformImpl { inner })
+    }
+
+    /// Attempt a dynamic cast to a normalization transform.
+    pub fn into_normalization_transform(self) -> Result<NormalizationTransformImpl> {
+        let inner = self.cast(faiss_NormalizationTransform_cast)?;
+        Ok(NormalizationTransformImpl { inner })
+    }
+
+    /// Attempt a dynamic cast to a centering transform.
+    pub fn into_centering_transform(self) -> Result<CenteringTransformImpl> {
+        let inner = self.cast(faiss_CenteringTransform_cast)?;
+        Ok(CenteringTransformImpl { inner })
+    }
+}
+
+/// Write a vector transform to the file at the given path.
+///
+/// # Error
+///
+/// This function returns an error if the path contains any byte with the value `\0` (since
+/// it cannot be converted to a C string), or if the internal writing operation fails.
+pub fn write_vector_transform<T, P>(vt: &T, path: P) -> Result<()>
+where
+    T: NativeVectorTransform,
+    P: AsRef<Path>,
+{
+    unsafe {
+        let f = path_to_cstring(path.as_ref())?;
+        faiss_try(faiss_write_VectorTransform_fname(
+            vt.inner_ptr(),
+            f.as_ptr(),
+        ))?;
+        Ok(())
+    }
+}
+
+/// Read a vector transform from the file at the given path.
+///
+/// # Error
+///
+/// This function returns an error if the path contains any byte with the value `\0` (since
+/// it cannot be converted to a C string), or if the internal reading operation fails.
+pub fn read_vector_transform<P>(path: P) -> Result<VectorTransformImpl>
+where
+    P: AsRef<Path>,
+{
+    unsafe {
+        let f = path_to_cstring(path.as_ref())?;
+        let mut inner = ptr::null_mut();
+        faiss_try(faiss_read_VectorTransform_fname(f.as_ptr(), &mut inner))?;
+        Ok(VectorTransformImpl { inner })
+    }
+}
+
 pub type RandomRotationMatrix = RandomRotationMatrixImpl;
 
 pub struct RandomRotationMatrixImpl {
@@ -463,4 +606,47 @@ mod tests {
         assert_eq!(rrt.d_out(), 256);
         assert_eq!(rrt.is_trained(), false);
     }
+
+    #[test]
+    fn pca_matrix_write_read() {
+        let mut pca = PCAMatrix::new(8, 4, 0., false).unwrap();
+        let some_data: Vec<f32> = (0..8 * 64).map(|i| ((i * 7) % 13) as f32).collect();
+        pca.train(64, &some_data).unwrap();
+        assert!(pca.is_trained());
+
+        let filepath = ::std::env::temp_dir().join("test_pca_matrix_write_read.vt");
+        write_vector_transform(&pca, &filepath).unwrap();
+        let vt = read_vector_transform(&filepath).unwrap();
+        ::std::fs::remove_file(&filepath).unwrap();
+        assert_eq!(vt.d_in(), 8);
+        assert_eq!(vt.d_out(), 4);
+        assert!(vt.is_trained());
+
+        let loaded = vt.into_pca_matrix().unwrap();
+        assert_eq!(loaded.eigen_power(), 0.);
+        assert!(!loaded.random_rotation());
+    }
+
+    #[test]
+    fn vector_transform_bad_cast() {
+        let centering = CenteringTransform::new(8).unwrap();
+        let filepath = ::std::env::temp_dir().join("test_vector_transform_bad_cast.vt");
+        write_vector_transform(&centering, &filepath).unwrap();
+        let vt = read_vector_transform(&filepath).unwrap();
+        ::std::fs::remove_file(&filepath).unwrap();
+        assert!(matches!(
+            vt.into_pca_matrix(),
+            Err(crate::error::Error::BadCast)
+        ));
+    }
+
+    #[test]
+    fn read_vector_transform_missing_file() {
+        let filepath = ::std::env::temp_dir().join("test_read_vector_transform_missing.vt");
+        assert!(read_vector_transform(&filepath).is_err());
+        assert_eq!(
+            read_vector_transform("bad\0path").err(),
+            Some(crate::error::Error::BadFilePath)
+        );
+    }
 }
@@ -0,0 +1,167 @@
+//! Version detection of the linked Faiss library.
+//!
+//! The bindings of this crate are generated from the C API of Faiss
+//! [`REQUIRED_VERSION`], and may rely on behavior which older releases
+//! do not have. Calling [`check_version`] at startup reports a library
+//! older than that as an error, instead of misbehaving later on.
+//!
+//! The check only compares the version reported by the library through
+//! `faiss_get_version`, which is itself missing from very old versions
+//! of the C API.
+//!
+//! [`REQUIRED_VERSION`]: constant.REQUIRED_VERSION.html
+//! [`check_version`]: fn.check_version.html
+//!
+//! # Examples
+//!
+//! ```
+//! # fn run() -> Result<(), Box<dyn std::error::Error>> {
+//! let version = faiss::version::check_version()?;
+//! assert!(version >= faiss::version::REQUIRED_VERSION);
+//! println!("Using Faiss {}", version);
+//! # Ok(())
+//! # }
+//! # run().unwrap();
+//! ```
+
+use crate::error::{Error, Result};
+use faiss_sys::*;
+use std::ffi::CStr;
+use std::fmt;
+use std::str::FromStr;
+
+/// The minimum version of the Faiss library supported by this crate.
+pub const REQUIRED_VERSION: Version = Version::new(1, 10, 0);
+
+/// A Faiss library version, in the form `major.minor.patch`.
+///
+/// Versions are ordered by precedence.
+#[derive(Debug, Copy, Clone, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct Version {
+    /// The major version number
+    pub major: u32,
+    /// The minor version number
+    pub minor: u32,
+    /// The patch version number
+    pub patch: u32,
+}
+
+impl Version {
+    /// Create a new version value.
+    pub const fn new(major: u32, minor: u32, patch: u32) -> Self {
+        Version {
+            major,
+            minor,
+            patch,
+        }
+    }
+}
+
+impl fmt::Display for Version {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}.{}.{}", self.major, self.minor, self.patch)
+    }
+}
+
+/// Parse a version string such as `1.10.0`.
+///
+/// The patch number may be omitted, and any pre-release or build suffix
+/// (such as in `1.10.0-rc1`) is ignored.
+impl FromStr for Version {
+    type Err = ParseVersionError;
+
+    fn from_str(s: &str) -> ::std::result::Result<Self, ParseVersionError> {
+        let s = s.trim();
+        let s = s.split(['-', '+']).next().unwrap_or(s);
+        let mut parts = s
+            .split('.')
+            .map(|p| p.parse::<u32>().map_err(|_| ParseVersionError));
+        let major = parts.next().ok_or(ParseVersionError)??;
+        let minor = parts.next().ok_or(ParseVersionError)??;
+        let patch = parts.next().transpose()?.unwrap_or(0);
+        if parts.next().is_some() {
+            return Err(ParseVersionError);
+        }
+        Ok(Version::new(major, minor, patch))
+    }
+}
+
+/// Error type for strings which are not valid versions.
+#[derive(Debug, Copy, Clone, Eq, Hash, PartialEq)]
+pub struct ParseVersionError;
+
+impl fmt::Display for ParseVersionError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("invalid version string")
+    }
+}
+
+impl std::error::Error for ParseVersionError {}
+
+/// Obtain the version string reported by the linked Faiss library.
+pub fn version_str() -> String {
+    unsafe {
+        let v = faiss_get_version();
+        if v.is_null() {
+            return String::new();
+        }
+        CStr::from_ptr(v).to_string_lossy().into_owned()
+    }
+}
+
+/// Obtain the version of the linked Faiss library.
+///
+/// Fails with [`Error::IncompatibleVersion`] if the version reported by the
+/// library cannot be parsed.
+///
+/// [`Error::IncompatibleVersion`]: ../error/enum.Error.html#variant.IncompatibleVersion
+pub fn version() -> Result<Version> {
+    let found = version_str();
+    found.parse().map_err(|_| Error::IncompatibleVersion {
+        found,
+        required: REQUIRED_VERSION,
+    })
+}
+
+/// Check that the linked Faiss library is compatible with this crate,
+/// returning its version.
+///
+/// Fails with [`Error::IncompatibleVersion`] if the library is older than
+/// [`REQUIRED_VERSION`], or if its version cannot be determined.
+///
+/// [`Error::IncompatibleVersion`]: ../error/enum.Error.html#variant.IncompatibleVersion
+/// [`REQUIRED_VERSION`]: constant.REQUIRED_VERSION.html
+pub fn check_version() -> Result<Version> {
+    let version = version()?;
+    if version < REQUIRED_VERSION {
+        return Err(Error::IncompatibleVersion {
+            found: version.to_string(),
+            required: REQUIRED_VERSION,
+        });
+    }
+    Ok(version)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_versions() {
+        assert_eq!("1.10.0".parse(), Ok(Version::new(1, 10, 0)));
+        assert_eq!("1.7".parse(), Ok(Version::new(1, 7, 0)));
+        assert_eq!(" 1.8.0-rc1\n".parse(), Ok(Version::new(1, 8, 0)));
+        assert_eq!("1.x.0".parse::<Version>(), Err(ParseVersionError));
+        assert_eq!("1.2.3.4".parse::<Version>(), Err(ParseVersionError));
+        assert_eq!("".parse::<Version>(), Err(ParseVersionError));
+        assert!(Version::new(1, 7, 2) < Version::new(1, 10, 0));
+        assert_eq!(Version::new(1, 10, 0).to_string(), "1.10.0");
+    }
+
+    #[test]
+    fn linked_version_is_compatible() {
+        let version = check_version().unwrap();
+        assert!(version >= REQUIRED_VERSION);
+        assert_eq!(version_str().parse(), Ok(version));
+    }
+}